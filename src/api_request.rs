@@ -0,0 +1,131 @@
+use crate::exports::edgee::components::data_collection::{EdgeeRequest, HttpMethod};
+
+/// Base URL of the LinkedIn Marketing API
+pub const API_BASE_URL: &str = "https://api.linkedin.com/rest";
+
+/// Version of the LinkedIn Marketing API, sent in the `LinkedIn-Version` header
+pub const LINKEDIN_VERSION: &str = "202506";
+
+/// Version of the Rest.li protocol, sent in the `X-Restli-Protocol-Version` header
+pub const RESTLI_PROTOCOL_VERSION: &str = "2.0.0";
+
+/// API Request
+///
+/// Request to the LinkedIn Marketing API, with the authentication, Rest.li and versioning headers.
+/// It can be sent by any HTTP client, or converted to an `EdgeeRequest`.
+#[derive(Debug, Clone)]
+pub struct ApiRequest {
+    pub method: HttpMethod,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl ApiRequest {
+    /// Request to a resource of the API, ex: `conversionEvents`
+    pub fn new(method: HttpMethod, resource: &str, access_token: &str, body: String) -> Self {
+        let headers = vec![
+            (
+                String::from("content-type"),
+                String::from("application/json"),
+            ),
+            (
+                String::from("X-Restli-Protocol-Version"),
+                String::from(RESTLI_PROTOCOL_VERSION),
+            ),
+            (
+                String::from("LinkedIn-Version"),
+                String::from(LINKEDIN_VERSION),
+            ),
+            (
+                String::from("Authorization"),
+                format!("Bearer {access_token}"),
+            ),
+        ];
+
+        Self {
+            method,
+            url: format!("{API_BASE_URL}/{resource}"),
+            headers,
+            body,
+        }
+    }
+
+    /// Send the request to another base URL than the LinkedIn API, ex: a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        if let Some(resource) = self.url.strip_prefix(API_BASE_URL) {
+            self.url = format!("{}{resource}", base_url.trim_end_matches('/'));
+        }
+        self
+    }
+
+    /// Set the Rest.li method of the request, ex: `BATCH_CREATE`
+    pub fn with_restli_method(mut self, restli_method: &str) -> Self {
+        self.headers
+            .push((String::from("X-RestLi-Method"), String::from(restli_method)));
+        self
+    }
+}
+
+impl From<ApiRequest> for EdgeeRequest {
+    fn from(request: ApiRequest) -> Self {
+        EdgeeRequest {
+            method: request.method,
+            url: request.url,
+            headers: request.headers,
+            forward_client_headers: false,
+            body: request.body,
+        }
+    }
+}
+
+/// Percent-encode a value used in a Rest.li URL, ex: the URNs of a compound key
+pub fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_request_headers() {
+        let request = ApiRequest::new(HttpMethod::Post, "conversions", "token", String::new())
+            .with_restli_method("BATCH_CREATE");
+        assert_eq!(request.url, "https://api.linkedin.com/rest/conversions");
+        assert!(request
+            .headers
+            .contains(&("LinkedIn-Version".to_string(), "202506".to_string())));
+        assert!(request
+            .headers
+            .contains(&("Authorization".to_string(), "Bearer token".to_string())));
+        assert!(request
+            .headers
+            .contains(&("X-RestLi-Method".to_string(), "BATCH_CREATE".to_string())));
+    }
+
+    #[test]
+    fn test_api_request_base_url() {
+        let request = ApiRequest::new(HttpMethod::Post, "conversionEvents", "token", String::new())
+            .with_base_url("http://127.0.0.1:8080/rest/");
+        assert_eq!(request.url, "http://127.0.0.1:8080/rest/conversionEvents");
+    }
+
+    #[test]
+    fn test_percent_encode() {
+        assert_eq!(
+            percent_encode("urn:li:sponsoredCampaign:123"),
+            "urn%3Ali%3AsponsoredCampaign%3A123"
+        );
+        assert_eq!(percent_encode("a b/é"), "a%20b%2F%C3%A9");
+    }
+}
@@ -0,0 +1,107 @@
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use crate::conversion::ConversionInput;
+use crate::skip::SkipReason;
+
+/// Campaign sources of sessions acquired from LinkedIn, ex: `utm_source=linkedin`
+const LINKEDIN_SOURCES: &[&str] = &["linkedin", "linkedin.com", "lnkd.in"];
+
+/// Attribution Mode
+///
+/// Which conversions are sent, depending on how the session was acquired.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AttributionMode {
+    /// All conversions are sent
+    #[default]
+    All,
+    /// Only conversions of sessions acquired from LinkedIn are sent
+    LinkedinOnly,
+}
+
+impl FromStr for AttributionMode {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim() {
+            "all" => Ok(AttributionMode::All),
+            "linkedin_only" => Ok(AttributionMode::LinkedinOnly),
+            _ => Err(anyhow!(
+                "Invalid attribution mode {value}, expected all or linkedin_only"
+            )),
+        }
+    }
+}
+
+impl AttributionMode {
+    pub fn check(&self, input: &ConversionInput) -> Result<(), SkipReason> {
+        if *self == AttributionMode::LinkedinOnly && !is_linkedin_attributed(input) {
+            let source = match input.campaign_source.trim() {
+                "" => "direct".to_string(),
+                source => source.to_string(),
+            };
+            return Err(SkipReason::NotLinkedinAttributed(source));
+        }
+        Ok(())
+    }
+}
+
+/// Whether the session was acquired from LinkedIn: its campaign source is LinkedIn,
+/// or it carries a LinkedIn click id
+pub fn is_linkedin_attributed(input: &ConversionInput) -> bool {
+    let source = input.campaign_source.trim().to_lowercase();
+    LINKEDIN_SOURCES.contains(&source.as_str())
+        || input
+            .li_fat_id
+            .as_ref()
+            .is_some_and(|li_fat_id| !li_fat_id.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_linkedin_attributed() {
+        for source in ["linkedin", "LinkedIn", "lnkd.in"] {
+            let input = ConversionInput {
+                campaign_source: source.to_string(),
+                ..ConversionInput::default()
+            };
+            assert!(
+                is_linkedin_attributed(&input),
+                "{source} should be LinkedIn"
+            );
+        }
+
+        let input = ConversionInput {
+            li_fat_id: Some("df2a1a2b-1c5e-4c4b-8f7d-1f6f4c3b2a10".to_string()),
+            ..ConversionInput::default()
+        };
+        assert!(is_linkedin_attributed(&input));
+
+        let input = ConversionInput {
+            campaign_source: "google".to_string(),
+            li_fat_id: Some(String::new()),
+            ..ConversionInput::default()
+        };
+        assert!(!is_linkedin_attributed(&input));
+    }
+
+    #[test]
+    fn test_attribution_mode_check() {
+        let input = ConversionInput::default();
+        assert_eq!(AttributionMode::All.check(&input), Ok(()));
+        assert_eq!(
+            AttributionMode::LinkedinOnly.check(&input),
+            Err(SkipReason::NotLinkedinAttributed("direct".to_string()))
+        );
+
+        assert_eq!(
+            "linkedin_only".parse::<AttributionMode>().unwrap(),
+            AttributionMode::LinkedinOnly
+        );
+        assert!("linkedin".parse::<AttributionMode>().is_err());
+    }
+}
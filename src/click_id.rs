@@ -0,0 +1,84 @@
+use crate::conversion::ConversionInput;
+
+/// User property holding the `li_fat_id` captured on the landing page
+pub const DEFAULT_LI_FAT_ID_PROPERTY: &str = "_li_fat_id";
+
+/// LinkedIn first party ads tracking UUID (`li_fat_id` click id) of a conversion.
+///
+/// The click id is read, in order of precedence:
+/// 1. from the `li_fat_id` query parameter of the current page,
+/// 2. from the `property` user property, persisted when the user landed on the site.
+///
+/// Values that are not UUIDs are ignored.
+pub fn resolve_li_fat_id(input: &ConversionInput, property: &str) -> Option<String> {
+    let from_page = input.li_fat_id.as_deref();
+    let from_user = input
+        .user_properties
+        .iter()
+        .find(|(key, _)| key == property)
+        .map(|(_, value)| value.as_str());
+
+    [from_page, from_user]
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .find(|li_fat_id| is_valid_li_fat_id(li_fat_id))
+        .map(String::from)
+}
+
+/// Whether the value looks like a UUID, ex: `df2a1a2b-1c5e-4c4b-8f7d-1f6f4c3b2a10`
+pub fn is_valid_li_fat_id(value: &str) -> bool {
+    let groups: Vec<&str> = value.split('-').collect();
+    groups.len() == 5
+        && groups
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(group, len)| group.len() == len && group.bytes().all(|c| c.is_ascii_hexdigit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LI_FAT_ID: &str = "df2a1a2b-1c5e-4c4b-8f7d-1f6f4c3b2a10";
+
+    #[test]
+    fn test_is_valid_li_fat_id() {
+        assert!(is_valid_li_fat_id(LI_FAT_ID));
+        assert!(is_valid_li_fat_id("DF2A1A2B-1C5E-4C4B-8F7D-1F6F4C3B2A10"));
+        for value in [
+            "",
+            "abc123",
+            "df2a1a2b1c5e4c4b8f7d1f6f4c3b2a10",
+            "df2a1a2b-1c5e-4c4b-8f7d-1f6f4c3b2a1",
+            "zf2a1a2b-1c5e-4c4b-8f7d-1f6f4c3b2a10",
+        ] {
+            assert!(!is_valid_li_fat_id(value), "{value} should be invalid");
+        }
+    }
+
+    #[test]
+    fn test_resolve_li_fat_id_precedence() {
+        let other = "0f2a1a2b-1c5e-4c4b-8f7d-1f6f4c3b2a10";
+        let input = ConversionInput {
+            li_fat_id: Some(LI_FAT_ID.to_string()),
+            user_properties: vec![("_li_fat_id".to_string(), other.to_string())],
+            ..ConversionInput::default()
+        };
+        assert_eq!(
+            resolve_li_fat_id(&input, DEFAULT_LI_FAT_ID_PROPERTY).as_deref(),
+            Some(LI_FAT_ID)
+        );
+
+        // invalid click id of the page falls back to the user property
+        let input = ConversionInput {
+            li_fat_id: Some("abc123".to_string()),
+            ..input
+        };
+        assert_eq!(
+            resolve_li_fat_id(&input, DEFAULT_LI_FAT_ID_PROPERTY).as_deref(),
+            Some(other)
+        );
+        assert_eq!(resolve_li_fat_id(&input, "li_fat_id"), None);
+    }
+}
@@ -0,0 +1,314 @@
+use anyhow::anyhow;
+
+use crate::click_id::resolve_li_fat_id;
+pub use crate::exports::edgee::components::data_collection::Consent;
+use crate::exports::edgee::components::data_collection::{Dict, Event, TrackData};
+use crate::extract_query_param;
+use crate::linkedin_payload::{LinkedinEvent, LinkedinPayload};
+use crate::routes::host_from_url;
+use crate::rules::{Decision, RuleInput};
+
+/// Conversion Input
+///
+/// Everything needed to build a LinkedIn conversion, independent from the Edgee event,
+/// so that the conversion mapping can be reused by any Rust code (backends, CLIs, tests).
+#[derive(Debug, Default, Clone)]
+pub struct ConversionInput {
+    /// Conversion rule URN, ex: `urn:lla:llaPartnerConversion:123`
+    pub conversion: String,
+    /// Time of the conversion, in milliseconds since the Unix epoch
+    pub timestamp_millis: i64,
+    /// Deduplication id of the conversion
+    pub event_id: String,
+    /// First party user id, sent as an external id
+    pub user_id: String,
+    /// Anonymous id of the user
+    pub anonymous_id: String,
+    /// Edgee id of the user, or the event id when missing, used for sampling when there is no user id
+    pub edgee_id: String,
+    /// Plain email of the user, hashed before being sent
+    pub email: Option<String>,
+    /// LinkedIn first party ads tracking UUID, from the `li_fat_id` click id of the page
+    pub li_fat_id: Option<String>,
+    /// User properties, ex: the `li_fat_id` persisted when the user landed on the site
+    pub user_properties: Vec<(String, String)>,
+    pub consent: Option<Consent>,
+    /// Conversion properties, ex: `value` and `currency`
+    pub properties: Vec<(String, String)>,
+    pub products: Vec<Dict>,
+    pub client_ip: String,
+    pub user_agent: String,
+    /// Host of the page, used to route the conversion to an ad account
+    pub page_host: String,
+    pub page_path: String,
+    /// Source of the campaign, used to route the conversion to an ad account
+    pub campaign_source: String,
+    pub campaign_medium: String,
+    pub campaign_name: String,
+    pub country_code: String,
+    pub session_count: u32,
+}
+
+impl ConversionInput {
+    /// Conversion input of an Edgee `track` event
+    pub fn from_track(edgee_event: &Event, data: &TrackData) -> Self {
+        let context = &edgee_event.context;
+
+        let event_id = data
+            .properties
+            .iter()
+            .find(|(key, id)| key == "event_id" && !id.is_empty())
+            .map(|(_, id)| id)
+            .unwrap_or(&edgee_event.uuid);
+        let edgee_id = if context.user.edgee_id.is_empty() {
+            &edgee_event.uuid
+        } else {
+            &context.user.edgee_id
+        };
+        let email = context
+            .user
+            .properties
+            .iter()
+            .find(|(key, _)| key == "email")
+            .map(|(_, email)| email.clone());
+
+        Self {
+            conversion: data.name.clone(),
+            timestamp_millis: edgee_event.timestamp_millis,
+            event_id: event_id.clone(),
+            user_id: context.user.user_id.clone(),
+            anonymous_id: context.user.anonymous_id.clone(),
+            edgee_id: edgee_id.clone(),
+            email,
+            li_fat_id: extract_query_param(&context.page.search, "li_fat_id").map(String::from),
+            user_properties: context.user.properties.clone(),
+            consent: edgee_event.consent,
+            properties: data.properties.clone(),
+            products: data.products.clone(),
+            client_ip: context.client.ip.clone(),
+            user_agent: context.client.user_agent.clone(),
+            page_host: host_from_url(&context.page.url).to_string(),
+            page_path: context.page.path.clone(),
+            campaign_source: context.campaign.source.clone(),
+            campaign_medium: context.campaign.medium.clone(),
+            campaign_name: context.campaign.name.clone(),
+            country_code: context.client.country_code.clone(),
+            session_count: context.session.session_count,
+        }
+    }
+
+    /// Stable key of the user, used to sample conversions
+    pub fn user_key(&self) -> &str {
+        if !self.user_id.is_empty() {
+            &self.user_id
+        } else if !self.edgee_id.is_empty() {
+            &self.edgee_id
+        } else {
+            &self.anonymous_id
+        }
+    }
+}
+
+/// Build the LinkedIn payload of a conversion.
+///
+/// Applies the routing, attribution mode, filtering rules, sampling and exclusions configured in the settings,
+/// and maps the conversion to one or more LinkedIn events.
+pub fn build_conversion(
+    input: &ConversionInput,
+    settings: Dict,
+) -> anyhow::Result<LinkedinPayload> {
+    if input.conversion.is_empty() {
+        return Err(anyhow!(
+            "Track name should be set to your conversion rule. ex: urn:lla:llaPartnerConversion:123"
+        ));
+    }
+
+    let mut linkedin_payload = LinkedinPayload::new(settings)?;
+
+    let input = &ConversionInput {
+        li_fat_id: resolve_li_fat_id(input, &linkedin_payload.li_fat_id_property),
+        ..input.clone()
+    };
+
+    let route = linkedin_payload.routes.select(input).cloned();
+    if let Some(access_token) = route.as_ref().and_then(|route| route.access_token.clone()) {
+        linkedin_payload.access_token = access_token;
+    }
+
+    linkedin_payload.attribution.check(input)?;
+
+    let decision = linkedin_payload.rules.evaluate(&RuleInput::new(input))?;
+    let conversion = match decision {
+        Decision::Send => input.conversion.clone(),
+        Decision::Rewrite(conversion) => conversion,
+    };
+    let conversion = match &route {
+        Some(route) => route.conversion(&conversion),
+        None => conversion,
+    };
+
+    linkedin_payload
+        .sampling
+        .check(&conversion, input.user_key())?;
+
+    let event = LinkedinEvent::new(
+        input,
+        &conversion,
+        &linkedin_payload.exclusions,
+        &linkedin_payload.external_id,
+    )?;
+    linkedin_payload.add_track_event(event, &input.properties, &input.products)?;
+
+    Ok(linkedin_payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_input() -> ConversionInput {
+        ConversionInput {
+            conversion: "urn:lla:llaPartnerConversion:123".to_string(),
+            timestamp_millis: 1714566600000,
+            event_id: "deal-1".to_string(),
+            user_id: "123".to_string(),
+            email: Some("test@test.com".to_string()),
+            consent: Some(Consent::Granted),
+            properties: vec![
+                ("value".to_string(), "10".to_string()),
+                ("currency".to_string(), "EUR".to_string()),
+            ],
+            client_ip: "81.2.69.142".to_string(),
+            ..ConversionInput::default()
+        }
+    }
+
+    fn sample_settings() -> Dict {
+        vec![(
+            "linkedin_access_token".to_string(),
+            "test_token".to_string(),
+        )]
+    }
+
+    #[test]
+    fn test_build_conversion() {
+        let payload = build_conversion(&sample_input(), sample_settings()).unwrap();
+        assert_eq!(payload.events.len(), 1);
+
+        let event = &payload.events[0];
+        assert_eq!(event.conversion, "urn:lla:llaPartnerConversion:123");
+        assert_eq!(event.event_time, 1714566600000);
+        assert_eq!(event.event_id, "deal-1");
+        assert_eq!(event.user_data.external_ids, vec!["123".to_string()]);
+        assert_eq!(
+            event.conversion_value.as_ref().unwrap().amount,
+            "10.00".to_string()
+        );
+    }
+
+    #[test]
+    fn test_build_conversion_without_name() {
+        let input = ConversionInput {
+            conversion: String::new(),
+            ..sample_input()
+        };
+        assert!(build_conversion(&input, sample_settings()).is_err());
+    }
+
+    #[test]
+    fn test_build_conversion_without_consent() {
+        let input = ConversionInput {
+            consent: Some(Consent::Denied),
+            ..sample_input()
+        };
+        let error = build_conversion(&input, sample_settings()).unwrap_err();
+        assert_eq!(error.to_string(), "Consent is not granted");
+    }
+
+    #[test]
+    fn test_build_conversion_with_route() {
+        let mut settings = sample_settings();
+        settings.push((
+            "linkedin_routes".to_string(),
+            r#"[{"host": "brand-b.com", "access_token": "token-b", "conversions": {"purchase": "urn:lla:llaPartnerConversion:2"}}]"#.to_string(),
+        ));
+        let input = ConversionInput {
+            conversion: "purchase".to_string(),
+            page_host: "www.brand-b.com".to_string(),
+            ..sample_input()
+        };
+
+        let payload = build_conversion(&input, settings.clone()).unwrap();
+        assert_eq!(payload.access_token, "token-b");
+        assert_eq!(
+            payload.events[0].conversion,
+            "urn:lla:llaPartnerConversion:2"
+        );
+
+        // default fallback
+        let payload = build_conversion(&sample_input(), settings).unwrap();
+        assert_eq!(payload.access_token, "test_token");
+        assert_eq!(
+            payload.events[0].conversion,
+            "urn:lla:llaPartnerConversion:123"
+        );
+    }
+
+    #[test]
+    fn test_build_conversion_linkedin_only() {
+        let mut settings = sample_settings();
+        settings.push((
+            "linkedin_attribution_mode".to_string(),
+            "linkedin_only".to_string(),
+        ));
+
+        let error = build_conversion(&sample_input(), settings.clone()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "skipped_not_linkedin_attributed: session was acquired from direct, not LinkedIn"
+        );
+
+        let input = ConversionInput {
+            campaign_source: "linkedin".to_string(),
+            ..sample_input()
+        };
+        assert!(build_conversion(&input, settings).is_ok());
+    }
+
+    #[test]
+    fn test_build_conversion_li_fat_id() {
+        let li_fat_id = "df2a1a2b-1c5e-4c4b-8f7d-1f6f4c3b2a10";
+        let input = ConversionInput {
+            user_properties: vec![("_li_fat_id".to_string(), li_fat_id.to_string())],
+            ..sample_input()
+        };
+        let payload = build_conversion(&input, sample_settings()).unwrap();
+        let user_ids = &payload.events[0].user_data.user_ids;
+        assert_eq!(
+            user_ids[0].id_type,
+            "LINKEDIN_FIRST_PARTY_ADS_TRACKING_UUID"
+        );
+        assert_eq!(user_ids[0].id_value, li_fat_id);
+
+        // invalid click ids are not sent
+        let input = ConversionInput {
+            li_fat_id: Some("abc123".to_string()),
+            ..sample_input()
+        };
+        let payload = build_conversion(&input, sample_settings()).unwrap();
+        let user_ids = &payload.events[0].user_data.user_ids;
+        assert_eq!(user_ids.len(), 1);
+        assert_eq!(user_ids[0].id_type, "SHA256_EMAIL");
+    }
+
+    #[test]
+    fn test_user_key() {
+        let input = ConversionInput {
+            user_id: String::new(),
+            anonymous_id: "abc".to_string(),
+            ..sample_input()
+        };
+        assert_eq!(input.user_key(), "abc");
+        assert_eq!(sample_input().user_key(), "123");
+    }
+}
@@ -0,0 +1,245 @@
+use anyhow::anyhow;
+use serde::Serialize;
+
+use crate::api_request::{percent_encode, ApiRequest};
+use crate::exports::edgee::components::data_collection::HttpMethod;
+
+/// Conversion Rule
+///
+/// Conversion rule tracked with the Conversions API, created with `POST /rest/conversions`.
+/// To know more about conversion rules, check the online documentation: https://learn.microsoft.com/en-us/linkedin/marketing/integrations/ads-reporting/conversions-api#setup-conversion-rules
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversionRule {
+    pub name: String,
+    /// Ad account URN, ex: `urn:li:sponsoredAccount:123`
+    pub account: String,
+    pub conversion_method: ConversionMethod,
+    #[serde(rename = "type")]
+    pub conversion_type: ConversionType,
+    pub attribution_type: AttributionType,
+    /// Attribution window after a click on an ad, in days
+    pub post_click_attribution_window_size: AttributionWindow,
+    /// Attribution window after an ad was seen, in days
+    pub view_through_attribution_window_size: AttributionWindow,
+    pub enabled: bool,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ConversionMethod {
+    ConversionsApi,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ConversionType {
+    AddToCart,
+    Download,
+    Install,
+    KeyPageView,
+    Lead,
+    Purchase,
+    SignUp,
+    BookAppointment,
+    RequestQuote,
+    Search,
+    StartCheckout,
+    SubmitApplication,
+    Other,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AttributionType {
+    /// Each campaign is credited with the last interaction with its ads
+    LastTouchByCampaign,
+    /// Only the last interaction across all campaigns is credited
+    LastTouchByConversion,
+}
+
+/// Attribution windows supported by LinkedIn, serialized as a number of days
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributionWindow {
+    OneDay,
+    SevenDays,
+    ThirtyDays,
+    NinetyDays,
+}
+
+impl AttributionWindow {
+    pub fn days(&self) -> u32 {
+        match self {
+            AttributionWindow::OneDay => 1,
+            AttributionWindow::SevenDays => 7,
+            AttributionWindow::ThirtyDays => 30,
+            AttributionWindow::NinetyDays => 90,
+        }
+    }
+}
+
+impl Serialize for AttributionWindow {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(self.days())
+    }
+}
+
+impl ConversionRule {
+    /// Conversion rule with LinkedIn default attribution: 30 days post-click, 7 days view-through
+    pub fn new(name: &str, account: &str, conversion_type: ConversionType) -> Self {
+        Self {
+            name: name.to_string(),
+            account: account.to_string(),
+            conversion_method: ConversionMethod::ConversionsApi,
+            conversion_type,
+            attribution_type: AttributionType::LastTouchByCampaign,
+            post_click_attribution_window_size: AttributionWindow::ThirtyDays,
+            view_through_attribution_window_size: AttributionWindow::SevenDays,
+            enabled: true,
+        }
+    }
+
+    pub fn attribution(
+        mut self,
+        attribution_type: AttributionType,
+        post_click: AttributionWindow,
+        view_through: AttributionWindow,
+    ) -> Self {
+        self.attribution_type = attribution_type;
+        self.post_click_attribution_window_size = post_click;
+        self.view_through_attribution_window_size = view_through;
+        self
+    }
+
+    /// `POST /rest/conversions` request creating the conversion rule.
+    /// The URN of the new rule is returned in the `x-linkedin-id` response header.
+    pub fn create_request(&self, access_token: &str) -> anyhow::Result<ApiRequest> {
+        if self.name.trim().is_empty() {
+            return Err(anyhow!("Conversion rule name is required"));
+        }
+        check_urn(&self.account, "urn:li:sponsoredAccount:")?;
+
+        let body = serde_json::to_string(self)?;
+        Ok(ApiRequest::new(
+            HttpMethod::Post,
+            "conversions",
+            access_token,
+            body,
+        ))
+    }
+}
+
+/// Campaign Conversion
+///
+/// Association of a conversion rule with a campaign, so that the campaign reports its conversions.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct CampaignConversion {
+    /// Campaign URN, ex: `urn:li:sponsoredCampaign:123`
+    pub campaign: String,
+    /// Conversion rule URN, ex: `urn:lla:llaPartnerConversion:456`
+    pub conversion: String,
+}
+
+impl CampaignConversion {
+    pub fn new(campaign: &str, conversion: &str) -> Self {
+        Self {
+            campaign: campaign.to_string(),
+            conversion: conversion.to_string(),
+        }
+    }
+
+    /// `PUT /rest/campaignConversions/(campaign:...,conversion:...)` request associating the
+    /// conversion rule with the campaign
+    pub fn associate_request(&self, access_token: &str) -> anyhow::Result<ApiRequest> {
+        check_urn(&self.campaign, "urn:li:sponsoredCampaign:")?;
+        check_urn(&self.conversion, "urn:lla:llaPartnerConversion:")?;
+
+        let key = format!(
+            "(campaign:{},conversion:{})",
+            percent_encode(&self.campaign),
+            percent_encode(&self.conversion)
+        );
+        let body = serde_json::to_string(self)?;
+        Ok(ApiRequest::new(
+            HttpMethod::Put,
+            &format!("campaignConversions/{key}"),
+            access_token,
+            body,
+        ))
+    }
+}
+
+/// URNs are `prefix` followed by a numeric id
+pub(crate) fn check_urn(urn: &str, prefix: &str) -> anyhow::Result<()> {
+    match urn.strip_prefix(prefix) {
+        Some(id) if !id.is_empty() && id.bytes().all(|c| c.is_ascii_digit()) => Ok(()),
+        _ => Err(anyhow!("Invalid URN {urn}, expected {prefix}<id>")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_conversion_rule_request() {
+        let rule = ConversionRule::new(
+            "Closed won deals",
+            "urn:li:sponsoredAccount:51234560",
+            ConversionType::Lead,
+        )
+        .attribution(
+            AttributionType::LastTouchByConversion,
+            AttributionWindow::NinetyDays,
+            AttributionWindow::OneDay,
+        );
+        let request = rule.create_request("token").unwrap();
+
+        assert_eq!(request.method, HttpMethod::Post);
+        assert_eq!(request.url, "https://api.linkedin.com/rest/conversions");
+        let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "name": "Closed won deals",
+                "account": "urn:li:sponsoredAccount:51234560",
+                "conversionMethod": "CONVERSIONS_API",
+                "type": "LEAD",
+                "attributionType": "LAST_TOUCH_BY_CONVERSION",
+                "postClickAttributionWindowSize": 90,
+                "viewThroughAttributionWindowSize": 1,
+                "enabled": true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_create_conversion_rule_request_invalid() {
+        let rule = ConversionRule::new("Lead", "123", ConversionType::Lead);
+        assert!(rule.create_request("token").is_err());
+
+        let rule = ConversionRule::new(" ", "urn:li:sponsoredAccount:1", ConversionType::Lead);
+        assert!(rule.create_request("token").is_err());
+    }
+
+    #[test]
+    fn test_associate_campaign_request() {
+        let association = CampaignConversion::new(
+            "urn:li:sponsoredCampaign:123",
+            "urn:lla:llaPartnerConversion:456",
+        );
+        let request = association.associate_request("token").unwrap();
+
+        assert_eq!(request.method, HttpMethod::Put);
+        assert_eq!(
+            request.url,
+            "https://api.linkedin.com/rest/campaignConversions/(campaign:urn%3Ali%3AsponsoredCampaign%3A123,conversion:urn%3Alla%3AllaPartnerConversion%3A456)"
+        );
+        let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(body["campaign"], "urn:li:sponsoredCampaign:123");
+        assert_eq!(body["conversion"], "urn:lla:llaPartnerConversion:456");
+
+        let association = CampaignConversion::new("123", "urn:lla:llaPartnerConversion:456");
+        assert!(association.associate_request("token").is_err());
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use rust_decimal::{Decimal, RoundingStrategy};
+
+/// ISO 4217 currencies without minor units
+const ZERO_DECIMAL_CURRENCIES: &[&str] = &[
+    "BIF", "CLP", "DJF", "GNF", "ISK", "JPY", "KMF", "KRW", "PYG", "RWF", "UGX", "UYI", "VND",
+    "VUV", "XAF", "XOF", "XPF",
+];
+
+/// ISO 4217 currencies with three minor units
+const THREE_DECIMAL_CURRENCIES: &[&str] = &["BHD", "IQD", "JOD", "KWD", "LYD", "OMR", "TND"];
+
+/// ISO 4217 currencies with four minor units
+const FOUR_DECIMAL_CURRENCIES: &[&str] = &["CLF", "UYW"];
+
+/// ISO 4217 currencies with two minor units
+const TWO_DECIMAL_CURRENCIES: &[&str] = &[
+    "AED", "AFN", "ALL", "AMD", "ANG", "AOA", "ARS", "AUD", "AWG", "AZN", "BAM", "BBD", "BDT",
+    "BGN", "BMD", "BND", "BOB", "BRL", "BSD", "BTN", "BWP", "BYN", "BZD", "CAD", "CDF", "CHF",
+    "CNY", "COP", "CRC", "CUP", "CVE", "CZK", "DKK", "DOP", "DZD", "EGP", "ERN", "ETB", "EUR",
+    "FJD", "FKP", "GBP", "GEL", "GHS", "GIP", "GMD", "GTQ", "GYD", "HKD", "HNL", "HTG", "HUF",
+    "IDR", "ILS", "INR", "IRR", "JMD", "KES", "KGS", "KHR", "KPW", "KYD", "KZT", "LAK", "LBP",
+    "LKR", "LRD", "LSL", "MAD", "MDL", "MGA", "MKD", "MMK", "MNT", "MOP", "MRU", "MUR", "MVR",
+    "MWK", "MXN", "MYR", "MZN", "NAD", "NGN", "NIO", "NOK", "NPR", "NZD", "PAB", "PEN", "PGK",
+    "PHP", "PKR", "PLN", "QAR", "RON", "RSD", "RUB", "SAR", "SBD", "SCR", "SDG", "SEK", "SGD",
+    "SHP", "SLE", "SOS", "SRD", "SSP", "STN", "SVC", "SYP", "SZL", "THB", "TJS", "TMT", "TOP",
+    "TRY", "TTD", "TWD", "TZS", "UAH", "USD", "UYU", "UZS", "VES", "WST", "XCD", "XCG", "YER",
+    "ZAR", "ZMW", "ZWG", "ZWL",
+];
+
+/// Number of minor units of an ISO 4217 currency, `None` if the currency is unknown.
+pub fn minor_units(currency: &str) -> Option<u32> {
+    if TWO_DECIMAL_CURRENCIES.contains(&currency) {
+        Some(2)
+    } else if ZERO_DECIMAL_CURRENCIES.contains(&currency) {
+        Some(0)
+    } else if THREE_DECIMAL_CURRENCIES.contains(&currency) {
+        Some(3)
+    } else if FOUR_DECIMAL_CURRENCIES.contains(&currency) {
+        Some(4)
+    } else {
+        None
+    }
+}
+
+/// Round an amount to the minor units of its currency (half away from zero).
+///
+/// The returned amount always has exactly as many decimals as the currency minor units.
+pub fn round_amount(amount: Decimal, currency: &str) -> Result<Decimal, CurrencyError> {
+    let units =
+        minor_units(currency).ok_or_else(|| CurrencyError::Unknown(currency.to_string()))?;
+    let mut amount = amount.round_dp_with_strategy(units, RoundingStrategy::MidpointAwayFromZero);
+    amount.rescale(units);
+    Ok(amount)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CurrencyError {
+    /// Not an ISO 4217 currency code
+    Unknown(String),
+    /// No conversion rate configured for this currency
+    MissingRate { from: String, to: String },
+}
+
+impl fmt::Display for CurrencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CurrencyError::Unknown(currency) => write!(f, "Unknown currency: {currency}"),
+            CurrencyError::MissingRate { from, to } => {
+                write!(f, "Missing currency rate from {from} to {to}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CurrencyError {}
+
+/// Currency Converter
+///
+/// Converts amounts to a target currency using a static table of rates,
+/// where a rate is the value of one unit of the currency in the target currency.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CurrencyConverter {
+    pub target: String,
+    pub rates: HashMap<String, Decimal>,
+}
+
+impl CurrencyConverter {
+    pub fn new(target: &str, rates: &HashMap<String, String>) -> anyhow::Result<Self> {
+        let target = normalize_currency(target)?;
+
+        let rates = rates
+            .iter()
+            .map(|(currency, rate)| {
+                let currency = normalize_currency(currency)?;
+                let rate = Decimal::from_str(rate.trim())
+                    .ok()
+                    .filter(|rate| rate.is_sign_positive() && !rate.is_zero())
+                    .ok_or_else(|| anyhow::anyhow!("Invalid rate for {currency}: {rate}"))?;
+                Ok((currency, rate))
+            })
+            .collect::<anyhow::Result<HashMap<String, Decimal>>>()?;
+
+        Ok(Self { target, rates })
+    }
+
+    /// Convert an amount to the target currency, rounded to its minor units.
+    pub fn convert(&self, amount: Decimal, currency: &str) -> Result<Decimal, CurrencyError> {
+        let currency = normalize_currency(currency)?;
+        if currency == self.target {
+            return round_amount(amount, &self.target);
+        }
+
+        let rate = self
+            .rates
+            .get(&currency)
+            .ok_or_else(|| CurrencyError::MissingRate {
+                from: currency.clone(),
+                to: self.target.clone(),
+            })?;
+
+        round_amount(amount.saturating_mul(*rate), &self.target)
+    }
+}
+
+/// Uppercase a currency code and check it is a known ISO 4217 currency.
+pub fn normalize_currency(currency: &str) -> Result<String, CurrencyError> {
+    let currency = currency.trim().to_uppercase();
+    match minor_units(&currency) {
+        Some(_) => Ok(currency),
+        None => Err(CurrencyError::Unknown(currency)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
+    fn converter() -> CurrencyConverter {
+        let rates = HashMap::from([
+            ("usd".to_string(), "0.92".to_string()),
+            ("GBP".to_string(), "1.17".to_string()),
+            ("JPY".to_string(), "0.0061".to_string()),
+        ]);
+        CurrencyConverter::new("EUR", &rates).unwrap()
+    }
+
+    #[test]
+    fn test_minor_units() {
+        assert_eq!(minor_units("EUR"), Some(2));
+        assert_eq!(minor_units("JPY"), Some(0));
+        assert_eq!(minor_units("KWD"), Some(3));
+        assert_eq!(minor_units("CLF"), Some(4));
+        assert_eq!(minor_units("XYZ"), None);
+    }
+
+    #[test]
+    fn test_round_amount() {
+        assert_eq!(round_amount(dec("25"), "USD").unwrap().to_string(), "25.00");
+        assert_eq!(
+            round_amount(dec("0.125"), "EUR").unwrap().to_string(),
+            "0.13"
+        );
+        assert_eq!(
+            round_amount(dec("1000.5"), "JPY").unwrap().to_string(),
+            "1001"
+        );
+        assert_eq!(
+            round_amount(dec("1.2345"), "BHD").unwrap().to_string(),
+            "1.235"
+        );
+        assert_eq!(
+            round_amount(dec("1"), "XYZ"),
+            Err(CurrencyError::Unknown("XYZ".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_no_float_drift() {
+        // 19.99 * 3 is 59.970000000000006 with f64
+        let amount = dec("19.99") * dec("3");
+        assert_eq!(round_amount(amount, "USD").unwrap().to_string(), "59.97");
+    }
+
+    #[test]
+    fn test_convert() {
+        let converter = converter();
+        assert_eq!(
+            converter.convert(dec("100"), "usd").unwrap().to_string(),
+            "92.00"
+        );
+        assert_eq!(
+            converter.convert(dec("10.10"), "GBP").unwrap().to_string(),
+            "11.82"
+        );
+        assert_eq!(
+            converter.convert(dec("1500"), "JPY").unwrap().to_string(),
+            "9.15"
+        );
+        assert_eq!(
+            converter.convert(dec("12.345"), "EUR").unwrap().to_string(),
+            "12.35"
+        );
+    }
+
+    #[test]
+    fn test_convert_errors() {
+        let converter = converter();
+        assert_eq!(
+            converter.convert(dec("1"), "CHF"),
+            Err(CurrencyError::MissingRate {
+                from: "CHF".to_string(),
+                to: "EUR".to_string()
+            })
+        );
+        assert_eq!(
+            converter.convert(dec("1"), "ABC"),
+            Err(CurrencyError::Unknown("ABC".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_converter_new_invalid() {
+        let rates = HashMap::from([("USD".to_string(), "abc".to_string())]);
+        assert!(CurrencyConverter::new("EUR", &rates).is_err());
+
+        let rates = HashMap::from([("USD".to_string(), "-1".to_string())]);
+        assert!(CurrencyConverter::new("EUR", &rates).is_err());
+
+        let rates = HashMap::from([("ABC".to_string(), "1".to_string())]);
+        assert!(CurrencyConverter::new("EUR", &rates).is_err());
+
+        assert!(CurrencyConverter::new("ABC", &HashMap::new()).is_err());
+    }
+}
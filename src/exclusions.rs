@@ -0,0 +1,281 @@
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use crate::ip::parse_ip;
+use crate::skip::SkipReason;
+
+/// Lowercase user agent fragments of known bots, crawlers, monitoring tools and automation frameworks.
+/// Bot names are matched with the separator that follows them (`googlebot/2.1`, `slackbot-linkexpanding`),
+/// as a bare `bot` also matches phone models such as `CUBOT X30`.
+const BOT_PATTERNS: &[&str] = &[
+    "bot/",
+    "bot;",
+    "bot-",
+    "crawl",
+    "spider",
+    "slurp",
+    "headless",
+    "phantomjs",
+    "puppeteer",
+    "playwright",
+    "selenium",
+    "webdriver",
+    "cypress",
+    "lighthouse",
+    "pingdom",
+    "uptime-kuma",
+    "scrapy",
+    "facebookexternalhit",
+    "curl/",
+    "wget/",
+    "python-requests",
+    "python-urllib",
+    "go-http-client",
+    "java/",
+    "okhttp",
+    "axios/",
+    "node-fetch",
+];
+
+/// Exclusions
+///
+/// Bot and internal traffic that should never be reported as LinkedIn conversions.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Exclusions {
+    /// Skip events whose user agent matches a known bot
+    pub bots: bool,
+    /// Skip events whose client IP is in one of these networks
+    pub ip_denylist: Vec<IpNetwork>,
+    /// Skip events whose email belongs to one of these domains (or their subdomains)
+    pub email_domains: Vec<String>,
+}
+
+impl Exclusions {
+    pub fn new(bots: bool, ip_denylist: &str, email_domains: &str) -> anyhow::Result<Self> {
+        let ip_denylist = split_list(ip_denylist)
+            .map(IpNetwork::from_str)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let email_domains = split_list(email_domains)
+            .map(|domain| domain.trim_start_matches('@').to_lowercase())
+            .collect();
+
+        Ok(Self {
+            bots,
+            ip_denylist,
+            email_domains,
+        })
+    }
+
+    pub fn check_user_agent(&self, user_agent: &str) -> Result<(), SkipReason> {
+        if self.bots && is_bot(user_agent) {
+            return Err(SkipReason::Bot(user_agent.to_string()));
+        }
+        Ok(())
+    }
+
+    pub fn check_ip(&self, ip: &str) -> Result<(), SkipReason> {
+        if self.ip_denylist.is_empty() {
+            return Ok(());
+        }
+        if let Some(ip) = parse_ip(ip) {
+            if self.ip_denylist.iter().any(|network| network.contains(ip)) {
+                return Err(SkipReason::DeniedIp(ip.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn check_email(&self, email: &str) -> Result<(), SkipReason> {
+        let Some((_, domain)) = email.trim().rsplit_once('@') else {
+            return Ok(());
+        };
+        let domain = domain.to_lowercase();
+        let denied = self.email_domains.iter().any(|denied| {
+            domain == *denied
+                || domain
+                    .strip_suffix(denied.as_str())
+                    .is_some_and(|subdomain| subdomain.ends_with('.'))
+        });
+        if denied {
+            return Err(SkipReason::DeniedEmailDomain(domain));
+        }
+        Ok(())
+    }
+}
+
+fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+}
+
+/// Whether the user agent belongs to a known bot. Empty user agents are not considered bots,
+/// as events sent server-side usually have none.
+pub fn is_bot(user_agent: &str) -> bool {
+    let user_agent = user_agent.to_lowercase();
+    BOT_PATTERNS
+        .iter()
+        .any(|pattern| user_agent.contains(pattern))
+}
+
+/// IP network in CIDR notation, ex: `10.0.0.0/8` or `2001:db8::/32`.
+/// A single IP address is a network of one address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNetwork {
+    pub addr: IpAddr,
+    pub prefix: u8,
+}
+
+impl IpNetwork {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpNetwork {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = match value.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (value, None),
+        };
+        let addr = parse_ip(addr).ok_or_else(|| anyhow!("Invalid IP network: {value}"))?;
+        let max_prefix = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .trim()
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix| *prefix <= max_prefix)
+                .ok_or_else(|| anyhow!("Invalid IP network: {value}"))?,
+            None => max_prefix,
+        };
+        Ok(Self { addr, prefix })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_bot() {
+        for user_agent in [
+            "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)",
+            "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) HeadlessChrome/120.0.0.0 Safari/537.36",
+            "curl/8.4.0",
+            "python-requests/2.31.0",
+            "LinkedInBot/1.0",
+            "Mozilla/5.0 (compatible; UptimeRobot/2.0; http://www.uptimerobot.com/)",
+            "Slackbot-LinkExpanding 1.0 (+https://api.slack.com/robots)",
+            "Uptime-Kuma/1.23.11",
+        ] {
+            assert!(is_bot(user_agent), "{user_agent} should be a bot");
+        }
+
+        for user_agent in [
+            "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+            "Chrome",
+            "",
+            // CUBOT phones
+            "Mozilla/5.0 (Linux; Android 10; CUBOT X30) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.6099.144 Mobile Safari/537.36",
+            "Mozilla/5.0 (Linux; Android 9; CUBOT_P30 Build/PPR1.180610.011) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/110.0.5481.153 Mobile Safari/537.36",
+        ] {
+            assert!(!is_bot(user_agent), "{user_agent} should not be a bot");
+        }
+    }
+
+    #[test]
+    fn test_check_user_agent() {
+        let exclusions = Exclusions::new(true, "", "").unwrap();
+        assert_eq!(
+            exclusions.check_user_agent("curl/8.4.0"),
+            Err(SkipReason::Bot("curl/8.4.0".to_string()))
+        );
+        assert_eq!(exclusions.check_user_agent("Chrome"), Ok(()));
+
+        let exclusions = Exclusions::default();
+        assert_eq!(exclusions.check_user_agent("curl/8.4.0"), Ok(()));
+    }
+
+    #[test]
+    fn test_ip_network() {
+        let network: IpNetwork = "10.0.0.0/8".parse().unwrap();
+        assert!(network.contains("10.1.2.3".parse().unwrap()));
+        assert!(!network.contains("11.0.0.1".parse().unwrap()));
+        assert!(!network.contains("::1".parse().unwrap()));
+
+        let network: IpNetwork = "2001:db8::/32".parse().unwrap();
+        assert!(network.contains("2001:db8:1::1".parse().unwrap()));
+        assert!(!network.contains("2001:db9::1".parse().unwrap()));
+
+        let network: IpNetwork = "81.2.69.142".parse().unwrap();
+        assert_eq!(network.prefix, 32);
+        assert!(network.contains("81.2.69.142".parse().unwrap()));
+        assert!(!network.contains("81.2.69.143".parse().unwrap()));
+
+        let network: IpNetwork = "0.0.0.0/0".parse().unwrap();
+        assert!(network.contains("81.2.69.142".parse().unwrap()));
+
+        assert!("10.0.0.0/33".parse::<IpNetwork>().is_err());
+        assert!("10.0.0/8".parse::<IpNetwork>().is_err());
+        assert!("::/129".parse::<IpNetwork>().is_err());
+    }
+
+    #[test]
+    fn test_check_ip() {
+        let exclusions = Exclusions::new(false, "192.168.0.0/16, 2a00:1450::/32", "").unwrap();
+        assert_eq!(
+            exclusions.check_ip("192.168.0.1"),
+            Err(SkipReason::DeniedIp("192.168.0.1".to_string()))
+        );
+        assert_eq!(
+            exclusions.check_ip("::ffff:192.168.0.1"),
+            Err(SkipReason::DeniedIp("192.168.0.1".to_string()))
+        );
+        assert_eq!(
+            exclusions.check_ip("2a00:1450:4007:80e::200e"),
+            Err(SkipReason::DeniedIp("2a00:1450:4007:80e::200e".to_string()))
+        );
+        assert_eq!(exclusions.check_ip("81.2.69.142"), Ok(()));
+        assert_eq!(exclusions.check_ip(""), Ok(()));
+
+        assert!(Exclusions::new(false, "not an ip", "").is_err());
+    }
+
+    #[test]
+    fn test_check_email() {
+        let exclusions = Exclusions::new(false, "", "@ourcompany.com, QA.example.org").unwrap();
+        assert_eq!(
+            exclusions.check_email("john@OurCompany.com"),
+            Err(SkipReason::DeniedEmailDomain("ourcompany.com".to_string()))
+        );
+        assert_eq!(
+            exclusions.check_email("john@eu.ourcompany.com"),
+            Err(SkipReason::DeniedEmailDomain(
+                "eu.ourcompany.com".to_string()
+            ))
+        );
+        assert_eq!(
+            exclusions.check_email("test@qa.example.org"),
+            Err(SkipReason::DeniedEmailDomain("qa.example.org".to_string()))
+        );
+        assert_eq!(exclusions.check_email("john@notourcompany.com"), Ok(()));
+        assert_eq!(exclusions.check_email("john@example.org"), Ok(()));
+        assert_eq!(exclusions.check_email("not an email"), Ok(()));
+    }
+}
@@ -0,0 +1,172 @@
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::conversion::ConversionInput;
+
+/// External Id Source
+///
+/// Which id of the user is sent to LinkedIn in `externalIds`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ExternalIdSource {
+    /// First party user id
+    #[default]
+    UserId,
+    /// Anonymous id of the user
+    AnonymousId,
+    /// Edgee id of the user
+    EdgeeId,
+}
+
+impl FromStr for ExternalIdSource {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim() {
+            "user_id" => Ok(ExternalIdSource::UserId),
+            "anonymous_id" => Ok(ExternalIdSource::AnonymousId),
+            "edgee_id" => Ok(ExternalIdSource::EdgeeId),
+            _ => Err(anyhow!(
+                "Invalid external id source {value}, expected user_id, anonymous_id or edgee_id"
+            )),
+        }
+    }
+}
+
+/// External Id
+///
+/// Id of the user sent in `externalIds`, optionally hashed with a secret salt (HMAC-SHA256 keyed with the salt),
+/// so that internal ids are not exposed to LinkedIn while the same user still gets the same external id.
+///
+/// LinkedIn matches the events of a user by their external id: changing the salt changes the external id
+/// of every user, and their new events are no longer matched with the previous ones.
+#[derive(Default, Clone)]
+pub struct ExternalId {
+    source: ExternalIdSource,
+    mac: Option<Hmac<Sha256>>,
+}
+
+// the salt is a secret, it is not printed
+impl std::fmt::Debug for ExternalId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExternalId")
+            .field("source", &self.source)
+            .field("salted", &self.mac.is_some())
+            .finish()
+    }
+}
+
+impl ExternalId {
+    pub fn new(source: ExternalIdSource, salt: Option<&str>) -> anyhow::Result<Self> {
+        let mac = match salt {
+            Some("") => return Err(anyhow!("salt must not be empty")),
+            Some(salt) => Some(
+                Hmac::<Sha256>::new_from_slice(salt.as_bytes())
+                    .map_err(|e| anyhow!("invalid salt: {e}"))?,
+            ),
+            None => None,
+        };
+        Ok(Self { source, mac })
+    }
+
+    /// External id of the user, or `None` when the user has no id from the source
+    pub fn resolve(&self, input: &ConversionInput) -> Option<String> {
+        let id = match self.source {
+            ExternalIdSource::UserId => &input.user_id,
+            ExternalIdSource::AnonymousId => &input.anonymous_id,
+            ExternalIdSource::EdgeeId => &input.edgee_id,
+        };
+        // Empty identifiers are rejected by LinkedIn, so they are not sent
+        if id.trim().is_empty() {
+            return None;
+        }
+
+        match &self.mac {
+            Some(mac) => {
+                let hash = mac.clone().chain_update(id.as_bytes()).finalize();
+                Some(format!("{:x}", hash.into_bytes()))
+            }
+            None => Some(id.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_input() -> ConversionInput {
+        ConversionInput {
+            user_id: "customer-42".to_string(),
+            anonymous_id: "anon-1".to_string(),
+            edgee_id: "edgee-1".to_string(),
+            ..ConversionInput::default()
+        }
+    }
+
+    #[test]
+    fn test_external_id_source_from_str() {
+        assert_eq!(
+            "user_id".parse::<ExternalIdSource>().unwrap(),
+            ExternalIdSource::UserId
+        );
+        assert_eq!(
+            " anonymous_id ".parse::<ExternalIdSource>().unwrap(),
+            ExternalIdSource::AnonymousId
+        );
+        assert_eq!(
+            "edgee_id".parse::<ExternalIdSource>().unwrap(),
+            ExternalIdSource::EdgeeId
+        );
+        assert!("email".parse::<ExternalIdSource>().is_err());
+    }
+
+    #[test]
+    fn test_external_id_sources() {
+        let input = sample_input();
+        for (source, expected) in [
+            (ExternalIdSource::UserId, "customer-42"),
+            (ExternalIdSource::AnonymousId, "anon-1"),
+            (ExternalIdSource::EdgeeId, "edgee-1"),
+        ] {
+            let external_id = ExternalId::new(source, None).unwrap();
+            assert_eq!(external_id.resolve(&input), Some(expected.to_string()));
+        }
+
+        let input = ConversionInput {
+            user_id: " ".to_string(),
+            ..sample_input()
+        };
+        assert_eq!(ExternalId::default().resolve(&input), None);
+    }
+
+    #[test]
+    fn test_external_id_salted() {
+        let external_id = ExternalId::new(ExternalIdSource::UserId, Some("secret")).unwrap();
+        let hashed = external_id.resolve(&sample_input()).unwrap();
+        // HMAC-SHA256 of the id, keyed with the salt
+        assert_eq!(
+            hashed,
+            "1e22f3f25e29689b1270b3301b610823fe5e83707c6602aa8ef5820fc19a3d68"
+        );
+        // consistent across events, and depending on the salt
+        assert_eq!(external_id.resolve(&sample_input()), Some(hashed.clone()));
+        let other = ExternalId::new(ExternalIdSource::UserId, Some("other")).unwrap();
+        assert_ne!(other.resolve(&sample_input()), Some(hashed));
+        // the salt and the id are not simply concatenated
+        let shifted = ExternalId::new(ExternalIdSource::UserId, Some("secretcustomer")).unwrap();
+        let input = ConversionInput {
+            user_id: "-42".to_string(),
+            ..sample_input()
+        };
+        assert_ne!(
+            shifted.resolve(&input),
+            external_id.resolve(&sample_input())
+        );
+        assert!(!format!("{external_id:?}").contains("secret"));
+
+        assert!(ExternalId::new(ExternalIdSource::UserId, Some("")).is_err());
+    }
+}
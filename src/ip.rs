@@ -0,0 +1,66 @@
+use std::net::IpAddr;
+
+/// Parse a client IP address.
+///
+/// Accepts IPv4, IPv6 (optionally bracketed or with a zone id) and IPv4-mapped IPv6 addresses,
+/// which are returned as plain IPv4.
+pub(crate) fn parse_ip(input: &str) -> Option<IpAddr> {
+    let input = input.trim();
+    let input = input
+        .strip_prefix('[')
+        .and_then(|ip| ip.strip_suffix(']'))
+        .unwrap_or(input);
+    let input = input.split('%').next().unwrap_or(input);
+
+    match input.parse::<IpAddr>().ok()? {
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => Some(IpAddr::V4(ip)),
+            None => Some(IpAddr::V6(ip)),
+        },
+        ip => Some(ip),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ip_v4() {
+        assert_eq!(
+            parse_ip("81.2.69.142"),
+            Some("81.2.69.142".parse().unwrap())
+        );
+        assert_eq!(
+            parse_ip(" 192.168.0.1 "),
+            Some("192.168.0.1".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_parse_ip_v6() {
+        assert_eq!(
+            parse_ip("2a00:1450:4007:80e::200e"),
+            Some("2a00:1450:4007:80e::200e".parse().unwrap())
+        );
+        assert_eq!(
+            parse_ip("[2a00:1450:4007:80e::200e]"),
+            Some("2a00:1450:4007:80e::200e".parse().unwrap())
+        );
+        assert_eq!(parse_ip("fe80::1%eth0"), Some("fe80::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_parse_ip_ipv4_mapped() {
+        assert_eq!(
+            parse_ip("::ffff:81.2.69.142"),
+            Some("81.2.69.142".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_parse_ip_invalid() {
+        assert_eq!(parse_ip(""), None);
+        assert_eq!(parse_ip("not an ip"), None);
+    }
+}
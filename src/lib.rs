@@ -1,12 +1,48 @@
-mod linkedin_payload;
+// A panic aborts the whole component invocation, errors must be returned instead
+#![cfg_attr(not(test), deny(clippy::unwrap_used, clippy::expect_used))]
+
+pub mod api_request;
+mod attribution;
+mod click_id;
+pub mod conversion;
+pub mod conversion_rules;
+mod currency;
+pub mod exclusions;
+pub mod external_id;
+mod ip;
+pub mod linkedin_payload;
+#[cfg(test)]
+mod mock_linkedin;
+pub mod oauth;
+#[cfg(test)]
+mod payload_schema;
+pub mod pii_guard;
+mod products;
+#[cfg(any(test, feature = "replay"))]
+pub mod replay;
+pub mod response;
+mod routes;
+mod rules;
+mod sampling;
+mod skip;
+#[cfg(test)]
+mod snapshot_tests;
+#[cfg(test)]
+mod test_support;
+pub mod timestamp;
 
 use crate::exports::edgee::components::data_collection::{
     Data, Dict, EdgeeRequest, Event, Guest, HttpMethod,
 };
-use linkedin_payload::{LinkedinEvent, LinkedinPayload};
+use api_request::ApiRequest;
+use conversion::{build_conversion, ConversionInput};
+use linkedin_payload::LinkedinPayload;
 
 wit_bindgen::generate!({world: "data-collection", path: ".edgee/wit", generate_all});
 
+// The component exports are only linked in the wasm build: their symbol names are rejected
+// by host linkers, and the host build is only used by tests and tools
+#[cfg(target_arch = "wasm32")]
 export!(LinkedinComponent);
 
 struct LinkedinComponent;
@@ -17,29 +53,12 @@ impl Guest for LinkedinComponent {
     }
 
     fn track(edgee_event: Event, settings: Dict) -> Result<EdgeeRequest, String> {
-        if let Data::Track(ref data) = edgee_event.data {
-            if data.name.is_empty() {
-                return Err("Track name should be set to your conversion rule. ex: urn:lla:llaPartnerConversion:123".to_string());
-            }
-
-            let mut linkedin_payload = LinkedinPayload::new(settings).map_err(|e| e.to_string())?;
-            let event_id = data
-                .properties
-                .iter()
-                .find(|(key, _)| key == "event_id")
-                .map(|(_, id)| id)
-                .unwrap_or(&edgee_event.uuid);
-
-            let li_fat_id = extract_query_param(&edgee_event.context.page.search, "li_fat_id");
-            let event = LinkedinEvent::new(&edgee_event, data.name.as_str(), event_id, li_fat_id)
-                .map_err(|e| e.to_string())?;
-
-            linkedin_payload.data = event;
-
-            Ok(build_edgee_request(linkedin_payload))
-        } else {
-            Err("Missing track data".to_string())
+        let (request, warnings) = track_request(edgee_event, settings)?;
+        // the data-collection world has no way to return warnings, stderr is shown in the Edgee debug output
+        for warning in warnings.iter() {
+            eprintln!("warning: {warning}");
         }
+        Ok(request)
     }
 
     fn user(_edgee_event: Event, _settings: Dict) -> Result<EdgeeRequest, String> {
@@ -49,10 +68,9 @@ impl Guest for LinkedinComponent {
 
 /// Extract a specific query parameter from a URL query string
 fn extract_query_param<'a>(query_string: &'a str, param_name: &'a str) -> Option<&'a str> {
+    let query_string = query_string.strip_prefix('?').unwrap_or(query_string);
     query_string.split('&').find_map(|pair| {
-        let mut parts = pair.split('=');
-        let key = parts.next()?;
-        let value = parts.next()?;
+        let (key, value) = pair.split_once('=')?;
         if key == param_name {
             Some(value)
         } else {
@@ -61,515 +79,421 @@ fn extract_query_param<'a>(query_string: &'a str, param_name: &'a str) -> Option
     })
 }
 
-fn build_edgee_request(linkedin_payload: LinkedinPayload) -> EdgeeRequest {
-    let headers = vec![
-        (
-            String::from("content-type"),
-            String::from("application/json"),
-        ),
-        (
-            String::from("X-Restli-Protocol-Version"),
-            String::from("2.0.0"),
-        ),
-        (String::from("LinkedIn-Version"), String::from("202506")),
-        (
-            String::from("Authorization"),
-            format!("Bearer {}", linkedin_payload.access_token),
-        ),
-    ];
-
-    let url = "https://api.linkedin.com/rest/conversionEvents";
-
-    EdgeeRequest {
-        method: HttpMethod::Post,
-        url: url.to_string(),
-        headers,
-        forward_client_headers: true,
-        body: serde_json::to_string(&linkedin_payload.data).unwrap(),
+/// Request of a track event, along with the warnings about the settings and the event
+fn track_request(
+    edgee_event: Event,
+    settings: Dict,
+) -> Result<(EdgeeRequest, Vec<String>), String> {
+    if let Data::Track(ref data) = edgee_event.data {
+        let input = ConversionInput::from_track(&edgee_event, data);
+        let linkedin_payload = build_conversion(&input, settings).map_err(|e| e.to_string())?;
+        build_edgee_request(linkedin_payload).map_err(|e| e.to_string())
+    } else {
+        Err("Missing track data".to_string())
     }
 }
 
+fn build_edgee_request(
+    linkedin_payload: LinkedinPayload,
+) -> anyhow::Result<(EdgeeRequest, Vec<String>)> {
+    let body = linkedin_payload
+        .body()
+        .map_err(|e| anyhow::anyhow!("Cannot serialize the LinkedIn payload: {e}"))?;
+    let (body, redacted) = linkedin_payload.pii_guard.apply(body)?;
+
+    let mut request = ApiRequest::new(
+        HttpMethod::Post,
+        "conversionEvents",
+        &linkedin_payload.access_token,
+        body,
+    );
+    if let Some(base_url) = &linkedin_payload.api_base_url {
+        request = request.with_base_url(base_url);
+    }
+    if linkedin_payload.is_batch() {
+        request = request.with_restli_method("BATCH_CREATE");
+    }
+
+    let mut warnings = linkedin_payload.warnings;
+    warnings.extend(
+        redacted
+            .iter()
+            .map(|finding| format!("pii_redacted: {finding} redacted")),
+    );
+    let edgee_request = EdgeeRequest {
+        forward_client_headers: true,
+        ..EdgeeRequest::from(request)
+    };
+    Ok((edgee_request, warnings))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::exports::edgee::components::data_collection::{
-        Campaign, Client, Context, EventType, PageData, Session, TrackData, UserData,
+    use crate::test_support::{
+        sample_products, sample_settings, settings, EventBuilder, CONVERSION,
     };
     use exports::edgee::components::data_collection::Consent;
     use pretty_assertions::assert_eq;
+    use proptest::prelude::*;
     use uuid::Uuid;
 
-    fn sample_user_data(edgee_id: String) -> UserData {
-        UserData {
-            user_id: "123".to_string(),
-            anonymous_id: "456".to_string(),
-            edgee_id,
-            properties: vec![
-                ("email".to_string(), "test@test.com".to_string()),
-                ("phone_number".to_string(), "+39 1231231231".to_string()),
-                ("first_name".to_string(), "John".to_string()),
-                ("last_name".to_string(), "Doe".to_string()),
-                ("gender".to_string(), "Male".to_string()),
-                ("date_of_birth".to_string(), "1979-12-31".to_string()),
-                ("city".to_string(), "Las Vegas".to_string()),
-                ("state".to_string(), "Nevada".to_string()),
-                ("zip_code".to_string(), "11111".to_string()),
-                ("country".to_string(), "USA".to_string()),
-                ("random_property".to_string(), "abc".to_string()), // will be ignored
-            ],
+    /// Track an event, checking the payload against the LinkedIn schema
+    fn track(event: Event, settings: Dict) -> Result<EdgeeRequest, String> {
+        let result = LinkedinComponent::track(event, settings);
+        if let Ok(request) = &result {
+            payload_schema::assert_valid_payload(&request.body);
         }
+        result
     }
 
-    fn sample_user_data_invalid_without_ids() -> UserData {
-        UserData {
-            user_id: "".to_string(),
-            anonymous_id: "".to_string(),
-            edgee_id: "abc".to_string(),
-            properties: vec![],
-        }
+    /// Settings with an access token and the given settings
+    fn settings_with(extra: &[(&str, &str)]) -> Dict {
+        let mut with = sample_settings();
+        with.extend(settings(extra));
+        with
     }
 
-    fn sample_user_data_invalid_without_email() -> UserData {
-        UserData {
-            user_id: "123".to_string(),
-            anonymous_id: "456".to_string(),
-            edgee_id: "abc".to_string(),
-            properties: vec![
-                // both missing
-                //("email".to_string(), "test@test.com".to_string()),
-                ("phone_number".to_string(), "+39 1231231231".to_string()),
-                ("first_name".to_string(), "John".to_string()),
-                ("last_name".to_string(), "Doe".to_string()),
-                ("gender".to_string(), "Male".to_string()),
-                ("date_of_birth".to_string(), "1979-12-31".to_string()),
-                ("city".to_string(), "Las Vegas".to_string()),
-                ("state".to_string(), "Nevada".to_string()),
-                ("zip_code".to_string(), "11111".to_string()),
-                ("country".to_string(), "USA".to_string()),
-                ("random_property".to_string(), "abc".to_string()), // will be ignored
-            ],
-        }
+    #[track_caller]
+    fn assert_page_not_implemented(event: Event, settings: Dict) {
+        assert_eq!(
+            LinkedinComponent::page(event, settings).unwrap_err(),
+            "Page event not implemented for this component"
+        );
     }
 
-    fn sample_context(edgee_id: String, locale: String, session_start: bool) -> Context {
-        Context {
-            page: sample_page_data(),
-            user: sample_user_data(edgee_id),
-            client: Client {
-                city: "Paris".to_string(),
-                ip: "192.168.0.1".to_string(),
-                locale,
-                timezone: "CET".to_string(),
-                user_agent: "Chrome".to_string(),
-                user_agent_architecture: "x86".to_string(),
-                user_agent_bitness: "64".to_string(),
-                user_agent_full_version_list: "abc".to_string(),
-                user_agent_version_list: "abc".to_string(),
-                user_agent_mobile: "mobile".to_string(),
-                user_agent_model: "don't know".to_string(),
-                os_name: "MacOS".to_string(),
-                os_version: "latest".to_string(),
-                screen_width: 1024,
-                screen_height: 768,
-                screen_density: 2.0,
-                continent: "Europe".to_string(),
-                country_code: "FR".to_string(),
-                country_name: "France".to_string(),
-                region: "West Europe".to_string(),
-            },
-            campaign: Campaign {
-                name: "random".to_string(),
-                source: "random".to_string(),
-                medium: "random".to_string(),
-                term: "random".to_string(),
-                content: "random".to_string(),
-                creative_format: "random".to_string(),
-                marketing_tactic: "random".to_string(),
-            },
-            session: Session {
-                session_id: "random".to_string(),
-                previous_session_id: "random".to_string(),
-                session_count: 2,
-                session_start,
-                first_seen: 123,
-                last_seen: 123,
-            },
-        }
+    #[track_caller]
+    fn assert_user_not_implemented(event: Event) {
+        assert_eq!(
+            LinkedinComponent::user(event, sample_settings()).unwrap_err(),
+            "User event not implemented for this component"
+        );
     }
 
-    fn sample_page_data() -> PageData {
-        PageData {
-            name: "page name".to_string(),
-            category: "category".to_string(),
-            keywords: vec!["value1".to_string(), "value2".into()],
-            title: "page title".to_string(),
-            url: "https://example.com/full-url?test=1".to_string(),
-            path: "/full-path".to_string(),
-            search: "?test=1".to_string(),
-            referrer: "https://example.com/another-page".to_string(),
-            properties: vec![
-                ("prop1".to_string(), "value1".to_string()),
-                ("prop2".to_string(), "10".to_string()),
-                ("prop3".to_string(), "true".to_string()),
-                ("prop4".to_string(), "false".to_string()),
-                ("currency".to_string(), "USD".to_string()),
-            ],
-        }
+    #[test]
+    fn page_with_consent() {
+        let event = EventBuilder::page().with_consent(Consent::Granted).build();
+        assert_page_not_implemented(event, sample_settings());
     }
 
-    fn sample_page_event(
-        consent: Option<Consent>,
-        edgee_id: String,
-        locale: String,
-        session_start: bool,
-    ) -> Event {
-        Event {
-            uuid: Uuid::new_v4().to_string(),
-            timestamp: 123,
-            timestamp_millis: 123,
-            timestamp_micros: 123,
-            event_type: EventType::Page,
-            data: Data::Page(sample_page_data()),
-            context: sample_context(edgee_id, locale, session_start),
-            consent,
-        }
+    #[test]
+    fn page_empty_consent() {
+        let event = EventBuilder::page().without_consent().build();
+        assert_page_not_implemented(event, sample_settings());
     }
 
-    fn sample_track_data(event_name: String) -> TrackData {
-        TrackData {
-            name: event_name,
-            products: vec![],
-            properties: vec![
-                ("prop1".to_string(), "value1".to_string()),
-                ("prop2".to_string(), "10".to_string()),
-                ("currency".to_string(), "USD".to_string()),
-            ],
-        }
+    #[test]
+    fn page_consent_denied_fails() {
+        let event = EventBuilder::page().with_consent(Consent::Denied).build();
+        assert_page_not_implemented(event, sample_settings());
     }
 
-    fn sample_track_event(
-        event_name: String,
-        consent: Option<Consent>,
-        edgee_id: String,
-        locale: String,
-        session_start: bool,
-    ) -> Event {
-        Event {
-            uuid: Uuid::new_v4().to_string(),
-            timestamp: 123,
-            timestamp_millis: 123,
-            timestamp_micros: 123,
-            event_type: EventType::Track,
-            data: Data::Track(sample_track_data(event_name)),
-            context: sample_context(edgee_id, locale, session_start),
-            consent,
-        }
+    #[test]
+    fn page_with_edgee_id_uuid() {
+        let event = EventBuilder::page()
+            .with_edgee_id(&Uuid::new_v4().to_string())
+            .build();
+        assert_page_not_implemented(event, sample_settings());
     }
 
-    fn sample_user_event(
-        consent: Option<Consent>,
-        edgee_id: String,
-        locale: String,
-        session_start: bool,
-    ) -> Event {
-        Event {
-            uuid: Uuid::new_v4().to_string(),
-            timestamp: 123,
-            timestamp_millis: 123,
-            timestamp_micros: 123,
-            event_type: EventType::User,
-            data: Data::User(sample_user_data(edgee_id.clone())),
-            context: sample_context(edgee_id, locale, session_start),
-            consent,
-        }
+    #[test]
+    fn page_with_empty_locale() {
+        let event = EventBuilder::page().with_locale("").build();
+        assert_page_not_implemented(event, sample_settings());
     }
 
-    fn sample_user_event_without_ids(
-        consent: Option<Consent>,
-        edgee_id: String,
-        locale: String,
-        session_start: bool,
-    ) -> Event {
-        let user_data = sample_user_data_invalid_without_ids();
-        Event {
-            uuid: Uuid::new_v4().to_string(),
-            timestamp: 123,
-            timestamp_millis: 123,
-            timestamp_micros: 123,
-            event_type: EventType::User,
-            data: Data::User(user_data.clone()),
-            context: sample_context(edgee_id, locale, session_start),
-            consent,
-        }
+    #[test]
+    fn page_not_session_start() {
+        let event = EventBuilder::page().with_session_start(false).build();
+        assert_page_not_implemented(event, sample_settings());
     }
 
-    fn sample_user_event_without_email(
-        consent: Option<Consent>,
-        edgee_id: String,
-        locale: String,
-        session_start: bool,
-    ) -> Event {
-        let user_data = sample_user_data_invalid_without_email();
-        Event {
-            uuid: Uuid::new_v4().to_string(),
-            timestamp: 123,
-            timestamp_millis: 123,
-            timestamp_micros: 123,
-            event_type: EventType::User,
-            data: Data::User(user_data.clone()),
-            context: sample_context(edgee_id, locale, session_start),
-            consent,
-        }
+    #[test]
+    fn page_without_access_token_fails() {
+        assert_page_not_implemented(EventBuilder::page().build(), vec![]);
     }
 
-    fn sample_settings() -> Vec<(String, String)> {
-        vec![
-            ("linkedin_access_token".to_string(), "abc".to_string()),
-            ("pinterest_ad_account_id".to_string(), "abc".to_string()),
-        ]
+    #[test]
+    fn track_with_consent() {
+        let event = EventBuilder::track(CONVERSION).build();
+        let edgee_request = track(event, sample_settings()).unwrap();
+        assert_eq!(edgee_request.method, HttpMethod::Post);
+        assert!(!edgee_request.body.is_empty());
+    }
+
+    fn find_header<'a>(edgee_request: &'a EdgeeRequest, name: &str) -> Option<&'a str> {
+        edgee_request
+            .headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
     }
 
     #[test]
-    fn page_with_consent() {
-        let event = sample_page_event(
-            Some(Consent::Granted),
-            "abc".to_string(),
-            "fr".to_string(),
-            true,
+    fn track_with_li_fat_id() {
+        let event = EventBuilder::track(CONVERSION)
+            .with_li_fat_id("df2a1a2b-1c5e-4c4b-8f7d-1f6f4c3b2a10")
+            .build();
+        let edgee_request = track(event, sample_settings()).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&edgee_request.body).unwrap();
+        assert_eq!(
+            body["user"]["userIds"][0]["idType"],
+            "LINKEDIN_FIRST_PARTY_ADS_TRACKING_UUID"
         );
-        let settings = sample_settings();
-        let result = LinkedinComponent::page(event, settings);
+        assert_eq!(body["user"]["userIds"][1]["idType"], "SHA256_EMAIL");
+    }
 
-        assert_eq!(result.is_err(), true);
+    #[test]
+    fn track_with_hashed_external_id() {
+        let event = EventBuilder::track(CONVERSION)
+            .with_anonymous_id("anon-1")
+            .build();
+        let settings = settings_with(&[
+            ("linkedin_external_id_source", "anonymous_id"),
+            ("linkedin_external_id_salt", "secret"),
+        ]);
+        let edgee_request = track(event, settings).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&edgee_request.body).unwrap();
         assert_eq!(
-            result
-                .clone()
-                .err()
-                .unwrap()
-                .to_string()
-                .contains("Page event not implemented for this component"),
-            true
+            body["user"]["externalIds"],
+            // HMAC-SHA256 of the anonymous id, keyed with the salt
+            serde_json::json!(["fe59bdb069eae8e2aab0c289a705dc50293eb7a26eaa7b501119c826ea3f5e94"])
         );
     }
 
     #[test]
-    fn page_empty_consent() {
-        let event = sample_page_event(
-            None, // no consent at all -> works fine
-            "abc".to_string(),
-            "fr".to_string(),
-            true,
-        );
-        let settings = sample_settings();
-        let result = LinkedinComponent::page(event, settings);
+    fn track_with_pii_rejected() {
+        let event = EventBuilder::track(CONVERSION)
+            .with_property("event_id", "john@example.com")
+            .build();
+        let settings = settings_with(&[("linkedin_pii_guard", "reject")]);
+        let result = track(event, settings);
+        assert_eq!(result.unwrap_err(), "pii_detected: email in eventId");
+    }
 
-        assert_eq!(result.is_err(), true);
+    #[test]
+    fn track_with_pii_redacted() {
+        let event = EventBuilder::track(CONVERSION)
+            .with_user_id("+33 6 12 34 56 78")
+            .build();
+        // redacted by default
+        let (edgee_request, warnings) = track_request(event, sample_settings()).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&edgee_request.body).unwrap();
         assert_eq!(
-            result
-                .clone()
-                .err()
-                .unwrap()
-                .to_string()
-                .contains("Page event not implemented for this component"),
-            true
+            body["user"]["externalIds"],
+            serde_json::json!([linkedin_payload::hash_value("+33 6 12 34 56 78")])
+        );
+        assert_eq!(
+            warnings,
+            ["pii_redacted: phone number in user.externalIds[0] redacted"]
         );
     }
 
     #[test]
-    fn page_consent_denied_fails() {
-        let event = sample_page_event(
-            Some(Consent::Denied),
-            "abc".to_string(),
-            "fr".to_string(),
-            true,
-        );
-        let settings = sample_settings();
-        let result = LinkedinComponent::page(event, settings);
+    fn track_with_value() {
+        let event = EventBuilder::track(CONVERSION).with_value("49.9").build();
+        let edgee_request = track(event, sample_settings()).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&edgee_request.body).unwrap();
+        assert_eq!(body["conversionValue"]["currencyCode"], "USD");
+        assert_eq!(body["conversionValue"]["amount"], "49.90");
+        assert_eq!(find_header(&edgee_request, "X-RestLi-Method"), None);
+    }
 
-        assert_eq!(result.is_err(), true);
+    #[test]
+    fn track_with_invalid_value() {
+        // the conversion is still sent, without its value
+        let event = EventBuilder::track(CONVERSION).with_value("abc").build();
+        let edgee_request = track(event, sample_settings()).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&edgee_request.body).unwrap();
+        assert_eq!(body["conversion"], CONVERSION);
+        assert_eq!(body.get("conversionValue"), None);
+    }
+
+    #[test]
+    fn track_with_product_conversions() {
+        let event = EventBuilder::track(CONVERSION)
+            .with_products(sample_products())
+            .build();
+        let settings = settings_with(&[
+            ("linkedin_value_from_products", "true"),
+            (
+                "linkedin_product_conversions",
+                "SKU-1=urn:lla:llaPartnerConversion:1,SKU-2=urn:lla:llaPartnerConversion:2",
+            ),
+        ]);
+        let edgee_request = track(event, settings).unwrap();
         assert_eq!(
-            result
-                .clone()
-                .err()
-                .unwrap()
-                .to_string()
-                .contains("Page event not implemented for this component"),
-            true
+            find_header(&edgee_request, "X-RestLi-Method"),
+            Some("BATCH_CREATE")
         );
+        let body: serde_json::Value = serde_json::from_str(&edgee_request.body).unwrap();
+        let elements = body["elements"].as_array().unwrap();
+        assert_eq!(elements.len(), 3);
+        assert_eq!(elements[0]["conversion"], CONVERSION);
+        assert_eq!(elements[0]["conversionValue"]["amount"], "25.00");
+        assert_eq!(elements[1]["conversion"], "urn:lla:llaPartnerConversion:1");
+        assert_eq!(elements[2]["conversion"], "urn:lla:llaPartnerConversion:2");
     }
 
     #[test]
-    fn page_with_edgee_id_uuid() {
-        let event = sample_page_event(None, Uuid::new_v4().to_string(), "fr".to_string(), true);
-        let settings = sample_settings();
-        let result = LinkedinComponent::page(event, settings);
-
-        assert_eq!(result.is_err(), true);
+    fn track_with_invalid_products_fails() {
+        let event = EventBuilder::track(CONVERSION)
+            .with_products(vec![settings(&[("sku", "SKU-1")])])
+            .build();
+        let settings = settings_with(&[("linkedin_value_from_products", "true")]);
+        let result = track(event, settings);
         assert_eq!(
-            result
-                .clone()
-                .err()
-                .unwrap()
-                .to_string()
-                .contains("Page event not implemented for this component"),
-            true
+            result.unwrap_err(),
+            "Invalid products: #0 (sku SKU-1): missing price, missing quantity"
         );
     }
 
     #[test]
-    fn page_with_empty_locale() {
-        let event = sample_page_event(None, Uuid::new_v4().to_string(), "".to_string(), true);
+    fn track_skipped_by_rule() {
+        let event = EventBuilder::track(CONVERSION).build();
+        let settings = settings_with(&[(
+            "linkedin_rules",
+            r#"[{"conditions": [{"field": "client.country_code", "operator": "equals", "value": "FR"}], "action": "skip", "reason": "internal team"}]"#,
+        )]);
+        let result = track(event, settings);
+        assert_eq!(result.unwrap_err(), "skipped_by_rule: internal team");
+    }
+
+    #[test]
+    fn track_rewritten_by_rule() {
+        let event = EventBuilder::track(CONVERSION)
+            .with_property("prop1", "value1")
+            .build();
+        let settings = settings_with(&[(
+            "linkedin_rules",
+            r#"[{"conditions": [{"field": "property.prop1", "operator": "equals", "value": "value1"}], "action": "rewrite", "conversion": "urn:lla:llaPartnerConversion:456"}]"#,
+        )]);
+        let edgee_request = track(event, settings).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&edgee_request.body).unwrap();
+        assert_eq!(body["conversion"], "urn:lla:llaPartnerConversion:456");
+    }
+
+    #[test]
+    fn track_with_invalid_rules_fails() {
+        let event = EventBuilder::track(CONVERSION).build();
+        let settings = settings_with(&[("linkedin_rules", "{")]);
+        let result = track(event, settings);
+        assert!(result
+            .unwrap_err()
+            .starts_with("Invalid linkedin_rules setting"));
+    }
 
-        let settings = sample_settings();
-        let result = LinkedinComponent::page(event, settings);
+    #[test]
+    fn track_from_bot_skipped() {
+        let event = EventBuilder::track(CONVERSION)
+            .with_user_agent(
+                "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)",
+            )
+            .build();
+        let settings = settings_with(&[("linkedin_exclude_bots", "true")]);
+        let result = track(event, settings);
+        assert!(result.unwrap_err().starts_with("skipped_bot:"));
+    }
 
-        assert_eq!(result.is_err(), true);
+    #[test]
+    fn track_from_denied_ip_skipped() {
+        let event = EventBuilder::track(CONVERSION)
+            .with_ip("192.168.0.1")
+            .build();
+        let settings = settings_with(&[("linkedin_ip_denylist", "192.168.0.0/16")]);
+        let result = track(event, settings);
         assert_eq!(
-            result
-                .clone()
-                .err()
-                .unwrap()
-                .to_string()
-                .contains("Page event not implemented for this component"),
-            true
+            result.unwrap_err(),
+            "skipped_denied_ip: client IP 192.168.0.1 is denied"
         );
     }
 
     #[test]
-    fn page_not_session_start() {
-        let event = sample_page_event(None, Uuid::new_v4().to_string(), "".to_string(), false);
-        let settings = sample_settings();
-        let result = LinkedinComponent::page(event, settings);
-
-        assert_eq!(result.is_err(), true);
+    fn track_from_denied_email_domain_skipped() {
+        let event = EventBuilder::track(CONVERSION)
+            .with_email("test@test.com")
+            .build();
+        let settings = settings_with(&[("linkedin_email_domain_denylist", "@test.com")]);
+        let result = track(event, settings);
         assert_eq!(
-            result
-                .clone()
-                .err()
-                .unwrap()
-                .to_string()
-                .contains("Page event not implemented for this component"),
-            true
+            result.unwrap_err(),
+            "skipped_denied_email_domain: email domain test.com is denied"
         );
     }
 
     #[test]
-    fn page_without_access_token_fails() {
-        let event = sample_page_event(None, "abc".to_string(), "fr".to_string(), true);
-        let settings: Vec<(String, String)> = vec![]; // empty
-        let result = LinkedinComponent::page(event, settings); // this should panic!
-        assert_eq!(result.is_err(), true);
+    fn track_with_exclusions_not_matching() {
+        let event = EventBuilder::track(CONVERSION).build();
+        let settings = settings_with(&[
+            ("linkedin_exclude_bots", "true"),
+            ("linkedin_ip_denylist", "10.0.0.0/8"),
+            ("linkedin_email_domain_denylist", "ourcompany.com"),
+        ]);
+        let result = track(event, settings);
+        assert!(result.is_ok());
     }
 
     #[test]
-    fn page_without_pixel_id_fails() {
-        let event = sample_page_event(None, "abc".to_string(), "fr".to_string(), true);
-        let settings: Vec<(String, String)> = vec![
-            ("linkedin_access_token".to_string(), "abc".to_string()), // only access token
-        ];
-        let result = LinkedinComponent::page(event, settings); // this should panic!
-        assert_eq!(result.is_err(), true);
+    fn track_with_sample_rate() {
+        let sample_rates = format!("{CONVERSION}=0.2");
+        let mut sent = 0;
+        for i in 0..1000 {
+            let event = EventBuilder::track(CONVERSION)
+                .with_user_id(&format!("user-{i}"))
+                .build();
+            let settings = settings_with(&[("linkedin_sample_rates", &sample_rates)]);
+            match track(event, settings) {
+                Ok(_) => sent += 1,
+                Err(e) => assert!(e.starts_with("skipped_sampled:")),
+            }
+        }
+        assert!((150..250).contains(&sent), "{sent} events sent");
     }
 
     #[test]
-    fn track_with_consent() {
-        let event = sample_track_event(
-            "event-name".to_string(),
-            Some(Consent::Granted),
-            "abc".to_string(),
-            "fr".to_string(),
-            true,
-        );
-        let settings = sample_settings();
-        let result = LinkedinComponent::track(event, settings);
-        println!("{:?}", result);
-        assert_eq!(result.clone().is_err(), false);
-        let edgee_request = result.unwrap();
-        assert_eq!(edgee_request.method, HttpMethod::Post);
-        assert!(!edgee_request.body.is_empty());
+    fn track_with_sample_rate_is_consistent_per_user() {
+        let sample_rates = format!("{CONVERSION}=0.5");
+        let results: Vec<bool> = (0..10)
+            .map(|_| {
+                let event = EventBuilder::track(CONVERSION)
+                    .with_uuid(&Uuid::new_v4().to_string())
+                    .build();
+                let settings = settings_with(&[("linkedin_sample_rates", &sample_rates)]);
+                track(event, settings).is_ok()
+            })
+            .collect();
+        assert!(results.iter().all(|sent| *sent == results[0]));
     }
 
     #[test]
     fn track_with_empty_name_fails() {
-        let event = sample_track_event(
-            "".to_string(),
-            Some(Consent::Granted),
-            "abc".to_string(),
-            "fr".to_string(),
-            true,
-        );
-        let settings = sample_settings();
-        let result = LinkedinComponent::track(event, settings);
-        assert_eq!(result.is_err(), true);
+        let event = EventBuilder::track("").build();
+        let result = track(event, sample_settings());
+        assert!(result.is_err());
     }
 
     #[test]
     fn user_event() {
-        let event = sample_user_event(
-            Some(Consent::Granted),
-            "abc".to_string(),
-            "fr".to_string(),
-            true,
-        );
-        let settings = sample_settings();
-        let result = LinkedinComponent::user(event, settings);
-
-        assert_eq!(result.clone().is_err(), true);
-        assert_eq!(
-            result
-                .clone()
-                .err()
-                .unwrap()
-                .to_string()
-                .contains("User event not implemented for this component"),
-            true
-        );
+        assert_user_not_implemented(EventBuilder::user().build());
     }
 
     #[test]
     fn user_event_without_ids_fails() {
-        let event = sample_user_event_without_ids(
-            Some(Consent::Granted),
-            "abc".to_string(),
-            "fr".to_string(),
-            true,
-        );
-        let settings = sample_settings();
-        let result = LinkedinComponent::user(event, settings);
-
-        assert_eq!(result.clone().is_err(), true);
-        assert_eq!(
-            result
-                .clone()
-                .err()
-                .unwrap()
-                .to_string()
-                .contains("User event not implemented for this component"),
-            true
-        );
+        let event = EventBuilder::user()
+            .with_user_id("")
+            .with_anonymous_id("")
+            .without_user_properties()
+            .build();
+        assert_user_not_implemented(event);
     }
 
     #[test]
     fn user_event_without_email_or_phone_fails() {
-        let event = sample_user_event_without_email(
-            Some(Consent::Granted),
-            "abc".to_string(),
-            "fr".to_string(),
-            true,
-        );
-        let settings = sample_settings();
-        let result = LinkedinComponent::user(event, settings);
-
-        assert_eq!(result.clone().is_err(), true);
-        assert_eq!(
-            result
-                .clone()
-                .err()
-                .unwrap()
-                .to_string()
-                .contains("User event not implemented for this component"),
-            true
-        );
+        let event = EventBuilder::user()
+            .without_email()
+            .without_user_property("phone_number")
+            .build();
+        assert_user_not_implemented(event);
     }
 
     #[test]
@@ -627,4 +551,141 @@ mod tests {
         let result = extract_query_param(query, "li_fat_id");
         assert_eq!(result, Some("abc-123_xyz"));
     }
+
+    #[test]
+    fn test_extract_query_param_leading_question_mark() {
+        let query = "?li_fat_id=abc123&other=test";
+        let result = extract_query_param(query, "li_fat_id");
+        assert_eq!(result, Some("abc123"));
+    }
+
+    #[test]
+    fn test_extract_query_param_value_with_equals() {
+        let query = "li_fat_id=abc=123";
+        let result = extract_query_param(query, "li_fat_id");
+        assert_eq!(result, Some("abc=123"));
+    }
+
+    fn arb_query() -> impl Strategy<Value = (Vec<(String, String)>, String)> {
+        let pairs = proptest::collection::vec(("[a-z_]{1,10}", "[^&]{0,20}"), 0..6);
+        (pairs, proptest::bool::ANY).prop_map(|(pairs, question_mark)| {
+            let query = pairs
+                .iter()
+                .map(|(key, value)| format!("{key}={value}"))
+                .collect::<Vec<_>>()
+                .join("&");
+            let query = if question_mark {
+                format!("?{query}")
+            } else {
+                query
+            };
+            (pairs, query)
+        })
+    }
+
+    fn arb_consent() -> impl Strategy<Value = Option<Consent>> {
+        prop_oneof![
+            Just(None),
+            Just(Some(Consent::Granted)),
+            Just(Some(Consent::Pending)),
+            Just(Some(Consent::Denied)),
+        ]
+    }
+
+    /// Email of the user, and whether it identifies the user
+    fn arb_email() -> impl Strategy<Value = (Option<String>, bool)> {
+        prop_oneof![
+            Just((None, false)),
+            " {0,3}".prop_map(|email| (Some(email), false)),
+            "[a-zA-Z0-9.+_-]{1,12}@[a-z0-9-]{1,12}\\.[a-z]{2,6}"
+                .prop_map(|email| (Some(email), true)),
+        ]
+    }
+
+    /// LinkedIn click id, and whether it is a valid UUID
+    fn arb_li_fat_id() -> impl Strategy<Value = (Option<String>, bool)> {
+        prop_oneof![
+            Just((None, false)),
+            "[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}"
+                .prop_map(|li_fat_id| (Some(li_fat_id), true)),
+            // too short, a missing digit, or not hexadecimal
+            "[0-9a-f]{0,7}".prop_map(|li_fat_id| (Some(li_fat_id), false)),
+            "[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{11}"
+                .prop_map(|li_fat_id| (Some(li_fat_id), false)),
+            "[g-z]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}"
+                .prop_map(|li_fat_id| (Some(li_fat_id), false)),
+        ]
+    }
+
+    prop_compose! {
+        /// Track event, with the error `track` must return for it, if any
+        fn arb_track_event()(
+            consent in arb_consent(),
+            user_id in "[ a-zA-Z0-9_-]{0,12}",
+            edgee_id in "[a-f0-9-]{0,36}",
+            (email, valid_email) in arb_email(),
+            (li_fat_id, valid_li_fat_id) in arb_li_fat_id(),
+            (_, query) in arb_query(),
+            ip in any::<[u8; 4]>(),
+            user_agent in "\\PC{0,40}",
+            value in proptest::option::of("-?[0-9]{0,6}(\\.[0-9]{0,3})?"),
+            event_id in proptest::option::of("[a-zA-Z0-9-]{0,16}"),
+        ) -> (Event, Option<&'static str>) {
+            let expected_error = match consent {
+                Some(Consent::Pending | Consent::Denied) => Some("Consent is not granted"),
+                _ if !valid_email && !valid_li_fat_id => Some("User properties must contain email"),
+                _ => None,
+            };
+
+            let mut builder = EventBuilder::track(CONVERSION)
+                .with_edgee_id(&edgee_id)
+                .with_user_id(&user_id)
+                .with_locale("en-US")
+                .with_session_start(false)
+                .without_user_properties();
+            builder = match consent {
+                Some(consent) => builder.with_consent(consent),
+                None => builder.without_consent(),
+            };
+            if let Some(email) = email {
+                builder = builder.with_email(&email);
+            }
+            let mut event = builder.build();
+            // the click id comes first, as the values of the random query are never UUIDs
+            event.context.page.search = match li_fat_id {
+                Some(li_fat_id) => format!("?li_fat_id={li_fat_id}&{}", query.trim_start_matches('?')),
+                None => query,
+            };
+            event.context.client.ip = ip.map(|byte| byte.to_string()).join(".");
+            event.context.client.user_agent = user_agent;
+            if let Data::Track(ref mut data) = event.data {
+                data.properties.extend(value.map(|value| ("value".to_string(), value)));
+                data.properties.extend(event_id.map(|id| ("event_id".to_string(), id)));
+            }
+            (event, expected_error)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn prop_extract_query_param_never_panics(query in "\\PC*", name in "\\PC*") {
+            let _ = extract_query_param(&query, &name);
+        }
+
+        #[test]
+        fn prop_extract_query_param_finds_first_value((pairs, query) in arb_query(), name in "[a-z_]{1,10}") {
+            let expected = pairs
+                .iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.as_str());
+            prop_assert_eq!(extract_query_param(&query, &name), expected);
+        }
+
+        #[test]
+        fn prop_track_builds_valid_payload((event, expected_error) in arb_track_event()) {
+            // `track` checks the payload against the LinkedIn schema
+            let result = track(event, sample_settings());
+            prop_assert_eq!(result.as_ref().err().map(String::as_str), expected_error);
+        }
+    }
 }
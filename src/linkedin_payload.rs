@@ -1,15 +1,65 @@
 use anyhow::anyhow;
+use rust_decimal::Decimal;
 use serde::Serialize;
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::exports::edgee::components::data_collection::{Consent, Dict, Event};
+use crate::attribution::AttributionMode;
+use crate::click_id::DEFAULT_LI_FAT_ID_PROPERTY;
+use crate::conversion::{Consent, ConversionInput};
+use crate::currency::{normalize_currency, round_amount, CurrencyConverter, CurrencyError};
+use crate::exclusions::Exclusions;
+use crate::exports::edgee::components::data_collection::Dict;
+use crate::external_id::{ExternalId, ExternalIdSource};
+use crate::oauth::{OAuthClient, TokenExpiry, DEFAULT_EXPIRY_WARNING_DAYS};
+use crate::pii_guard::PiiGuard;
+use crate::products::{parse_products, products_value};
+use crate::routes::Routes;
+use crate::rules::Rules;
+use crate::sampling::Sampling;
+use crate::timestamp::parse_timestamp_millis;
 
 #[derive(Serialize, Debug, Default)]
-pub(crate) struct LinkedinPayload {
-    pub data: LinkedinEvent,
+pub struct LinkedinPayload {
+    pub events: Vec<LinkedinEvent>,
     #[serde(skip)]
     pub access_token: String,
+    #[serde(skip)]
+    pub value_from_products: bool,
+    #[serde(skip)]
+    pub product_conversions: HashMap<String, String>,
+    #[serde(skip)]
+    pub currency_converter: Option<CurrencyConverter>,
+    #[serde(skip)]
+    pub rules: Rules,
+    #[serde(skip)]
+    pub exclusions: Exclusions,
+    #[serde(skip)]
+    pub sampling: Sampling,
+    #[serde(skip)]
+    pub routes: Routes,
+    #[serde(skip)]
+    pub attribution: AttributionMode,
+    #[serde(skip)]
+    pub li_fat_id_property: String,
+    #[serde(skip)]
+    pub external_id: ExternalId,
+    #[serde(skip)]
+    pub pii_guard: PiiGuard,
+    /// Base URL of the LinkedIn API, only overridden by the tests to send the requests to a mock server
+    #[serde(skip)]
+    pub api_base_url: Option<String>,
+    /// Warnings about the settings and the events, returned along with the request
+    #[serde(skip)]
+    pub warnings: Vec<String>,
+}
+
+/// Batch of LinkedIn events, sent with the `BATCH_CREATE` Rest.li method.
+#[derive(Serialize, Debug)]
+struct BatchCreate<'a> {
+    elements: &'a [LinkedinEvent],
 }
 
 impl LinkedinPayload {
@@ -25,11 +75,273 @@ impl LinkedinPayload {
         }
         .to_string();
 
+        let value_from_products = parse_bool_setting(&cred, "linkedin_value_from_products");
+        let product_conversions = match cred.get("linkedin_product_conversions") {
+            Some(value) => parse_map_setting(value)
+                .map_err(|e| anyhow!("Invalid linkedin_product_conversions setting: {e}"))?,
+            None => HashMap::new(),
+        };
+        let currency_converter = match (
+            cred.get("linkedin_currency"),
+            cred.get("linkedin_currency_rates"),
+        ) {
+            (Some(target), rates) => {
+                let rates = match rates {
+                    Some(rates) => parse_map_setting(rates)
+                        .map_err(|e| anyhow!("Invalid linkedin_currency_rates setting: {e}"))?,
+                    None => HashMap::new(),
+                };
+                Some(
+                    CurrencyConverter::new(target, &rates)
+                        .map_err(|e| anyhow!("Invalid currency settings: {e}"))?,
+                )
+            }
+            (None, Some(_)) => {
+                return Err(anyhow!(
+                    "linkedin_currency must be set to use linkedin_currency_rates"
+                ))
+            }
+            (None, None) => None,
+        };
+        let rules = match cred.get("linkedin_rules") {
+            Some(value) => {
+                Rules::parse(value).map_err(|e| anyhow!("Invalid linkedin_rules setting: {e}"))?
+            }
+            None => Rules::default(),
+        };
+        let exclusions = Exclusions::new(
+            parse_bool_setting(&cred, "linkedin_exclude_bots"),
+            cred.get("linkedin_ip_denylist")
+                .map(String::as_str)
+                .unwrap_or_default(),
+            cred.get("linkedin_email_domain_denylist")
+                .map(String::as_str)
+                .unwrap_or_default(),
+        )
+        .map_err(|e| anyhow!("Invalid exclusion settings: {e}"))?;
+        let sampling = match cred.get("linkedin_sample_rates") {
+            Some(value) => parse_map_setting(value)
+                .and_then(|rates| Sampling::new(&rates))
+                .map_err(|e| anyhow!("Invalid linkedin_sample_rates setting: {e}"))?,
+            None => Sampling::default(),
+        };
+        let routes = match cred.get("linkedin_routes") {
+            Some(value) => {
+                Routes::parse(value).map_err(|e| anyhow!("Invalid linkedin_routes setting: {e}"))?
+            }
+            None => Routes::default(),
+        };
+        let attribution = match cred.get("linkedin_attribution_mode") {
+            Some(value) => AttributionMode::from_str(value)?,
+            None => AttributionMode::default(),
+        };
+        let li_fat_id_property = cred
+            .get("linkedin_li_fat_id_property")
+            .map(|property| property.trim().to_string())
+            .unwrap_or_else(|| DEFAULT_LI_FAT_ID_PROPERTY.to_string());
+        // the refresh requests are sent outside of the component, the credentials are only validated here
+        OAuthClient::new(
+            cred.get("linkedin_client_id").map(String::as_str),
+            cred.get("linkedin_client_secret").map(String::as_str),
+            cred.get("linkedin_refresh_token").map(String::as_str),
+        )?;
+
+        let external_id_source = match cred.get("linkedin_external_id_source") {
+            Some(value) => ExternalIdSource::from_str(value)?,
+            None => ExternalIdSource::default(),
+        };
+        let external_id = ExternalId::new(
+            external_id_source,
+            cred.get("linkedin_external_id_salt").map(String::as_str),
+        )
+        .map_err(|e| anyhow!("Invalid linkedin_external_id_salt setting: {e}"))?;
+
+        let pii_guard = match cred.get("linkedin_pii_guard") {
+            Some(value) => PiiGuard::from_str(value)?,
+            None => PiiGuard::default(),
+        };
+
+        let mut warnings = vec![];
+        if let Some(expires_at) = cred.get("linkedin_access_token_expires_at") {
+            let expires_at = parse_timestamp_millis(expires_at).ok_or_else(|| {
+                anyhow!("Invalid linkedin_access_token_expires_at setting: {expires_at}")
+            })?;
+            let warning_days = match cred.get("linkedin_access_token_warning_days") {
+                Some(days) => days
+                    .trim()
+                    .parse::<i64>()
+                    .ok()
+                    .filter(|days| *days >= 0)
+                    .ok_or_else(|| {
+                        anyhow!("Invalid linkedin_access_token_warning_days setting: {days}")
+                    })?,
+                None => DEFAULT_EXPIRY_WARNING_DAYS,
+            };
+            let expiry = TokenExpiry {
+                expires_at,
+                warning_days,
+            };
+            warnings.extend(expiry.check(now_millis())?);
+        }
+
         Ok(Self {
             access_token,
+            value_from_products,
+            product_conversions,
+            currency_converter,
+            rules,
+            exclusions,
+            sampling,
+            routes,
+            attribution,
+            li_fat_id_property,
+            external_id,
+            pii_guard,
+            warnings,
             ..LinkedinPayload::default()
         })
     }
+
+    /// Add a conversion event to the payload.
+    ///
+    /// The conversion value is read from the `value` and `currency` properties,
+    /// and is left out with a warning when the value is not a number or its currency is missing or unknown.
+    /// Depending on the settings, the conversion value is derived from the products,
+    /// and one additional conversion is added for each product whose SKU is mapped to a conversion rule.
+    pub fn add_track_event(
+        &mut self,
+        mut event: LinkedinEvent,
+        properties: &[(String, String)],
+        products: &[Dict],
+    ) -> anyhow::Result<()> {
+        let property = |name: &str| {
+            properties
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.trim())
+        };
+        let currency = property("currency");
+
+        // the conversion still counts without its value, so an invalid value is only reported
+        match (property("value"), currency) {
+            (None, _) => {}
+            (Some(value), None) => self.warnings.push(format!(
+                "missing_conversion_currency: conversion value {value} has no currency, sent without value"
+            )),
+            (Some(value), Some(currency)) => match Decimal::from_str(value) {
+                Ok(amount) => event.conversion_value = self.conversion_value(amount, currency),
+                Err(_) => self.warnings.push(format!(
+                    "invalid_conversion_value: conversion value {value} is not a number, sent without value"
+                )),
+            },
+        }
+
+        if products.is_empty() || (!self.value_from_products && self.product_conversions.is_empty())
+        {
+            self.events.push(event);
+            return Ok(());
+        }
+
+        let products = parse_products(products)?;
+        if currency.is_none() {
+            self.warnings.push(
+                "missing_conversion_currency: products have no currency, sent without value"
+                    .to_string(),
+            );
+        }
+
+        if self.value_from_products {
+            event.conversion_value = match currency {
+                Some(currency) => self.conversion_value(products_value(&products), currency),
+                None => None,
+            };
+        }
+
+        let mut product_events = vec![];
+        for product in products.iter() {
+            if let Some(conversion) = self.product_conversions.get(&product.sku).cloned() {
+                let conversion_value = match currency {
+                    Some(currency) => self.conversion_value(product.value(), currency),
+                    None => None,
+                };
+                product_events.push(LinkedinEvent {
+                    conversion,
+                    event_id: format!("{}-{}", event.event_id, product.sku),
+                    conversion_value,
+                    ..event.clone()
+                });
+            }
+        }
+
+        self.events.push(event);
+        self.events.extend(product_events);
+
+        Ok(())
+    }
+
+    /// Conversion value of an amount, left out with a warning when its currency is unknown
+    /// or has no rate to the target currency.
+    fn conversion_value(&mut self, amount: Decimal, currency: &str) -> Option<ConversionValue> {
+        match ConversionValue::new(amount, currency, self.currency_converter.as_ref()) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.warnings.push(format!(
+                    "invalid_conversion_currency: {e}, sent without value"
+                ));
+                None
+            }
+        }
+    }
+
+    /// Several events are sent as a single `BATCH_CREATE` request.
+    pub fn is_batch(&self) -> bool {
+        self.events.len() > 1
+    }
+
+    /// JSON body of the request sent to LinkedIn CAPI.
+    pub fn body(&self) -> serde_json::Result<String> {
+        match self.events.as_slice() {
+            [event] => serde_json::to_string(event),
+            _ => self.batch_body(),
+        }
+    }
+
+    /// JSON body of a `BATCH_CREATE` request, whatever the number of events.
+    pub fn batch_body(&self) -> serde_json::Result<String> {
+        serde_json::to_string(&BatchCreate {
+            elements: &self.events,
+        })
+    }
+}
+
+/// Current time, in milliseconds since the Unix epoch
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+/// Boolean settings are disabled unless explicitly set to `true`.
+fn parse_bool_setting(cred: &HashMap<String, String>, key: &str) -> bool {
+    cred.get(key)
+        .is_some_and(|value| value.trim().eq_ignore_ascii_case("true"))
+}
+
+/// Map settings are written as comma-separated `key=value` pairs.
+/// ex: `SKU-1=urn:lla:llaPartnerConversion:1,SKU-2=urn:lla:llaPartnerConversion:2`
+pub(crate) fn parse_map_setting(value: &str) -> anyhow::Result<HashMap<String, String>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| match entry.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() && !value.trim().is_empty() => {
+                Ok((key.trim().to_string(), value.trim().to_string()))
+            }
+            _ => Err(anyhow!("expected key=value, got '{entry}'")),
+        })
+        .collect()
 }
 
 /// LinkedIn Event
@@ -39,22 +351,59 @@ impl LinkedinPayload {
 ///
 /// There is one way of tracking conversions using this component:
 /// - Personalized events, which are user actions defined by you as conversation rules on the linkedin api interface and recorded by calling by calling a `track`event with a custom event name.
-#[derive(Serialize, Debug, Default)]
+#[derive(Serialize, Debug, Default, Clone)]
 pub struct LinkedinEvent {
     pub conversion: String,
     #[serde(rename = "conversionHappenedAt")]
     pub event_time: i64,
+    #[serde(rename = "conversionValue", skip_serializing_if = "Option::is_none")]
+    pub conversion_value: Option<ConversionValue>,
     #[serde(rename = "user")]
     pub user_data: UserData,
     #[serde(rename = "eventId")]
     pub event_id: String,
 }
 
+// Conversion Value
+//
+// Monetary value of the conversion.
+// To know more about the conversion value structure, check the online documentation: https://learn.microsoft.com/en-us/linkedin/marketing/integrations/ads-reporting/conversions-api?view=li-lms-2024-11&tabs=http#conversionevent
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub struct ConversionValue {
+    #[serde(rename = "currencyCode")]
+    pub currency_code: String,
+    pub amount: String,
+}
+
+impl ConversionValue {
+    /// Build a conversion value, converted to the target currency when a converter is set,
+    /// and rounded to the minor units of the currency.
+    pub fn new(
+        amount: Decimal,
+        currency_code: &str,
+        converter: Option<&CurrencyConverter>,
+    ) -> Result<Self, CurrencyError> {
+        match converter {
+            Some(converter) => Ok(Self {
+                amount: converter.convert(amount, currency_code)?.to_string(),
+                currency_code: converter.target.clone(),
+            }),
+            None => {
+                let currency_code = normalize_currency(currency_code)?;
+                Ok(Self {
+                    amount: round_amount(amount, &currency_code)?.to_string(),
+                    currency_code,
+                })
+            }
+        }
+    }
+}
+
 // User Data
 //
 // This is the user data that will be sent to LinkedIn CAPI.
 // To know more about the user data structure, check the online documentation: https://learn.microsoft.com/en-us/linkedin/marketing/integrations/ads-reporting/conversions-api?view=li-lms-2024-11&tabs=http#conversioneventuser
-#[derive(Serialize, Debug, Default)]
+#[derive(Serialize, Debug, Default, Clone)]
 pub struct UserData {
     #[serde(rename = "userIds")]
     pub user_ids: Vec<UserId>,
@@ -62,7 +411,7 @@ pub struct UserData {
     pub external_ids: Vec<String>,
 }
 
-#[derive(Serialize, Debug, Default)]
+#[derive(Serialize, Debug, Default, Clone)]
 pub struct UserId {
     #[serde(rename = "idType")]
     pub id_type: String,
@@ -72,47 +421,52 @@ pub struct UserId {
 
 impl LinkedinEvent {
     pub fn new(
-        edgee_event: &Event,
-        event_name: &str,
-        event_id: &str,
-        li_fat_id: Option<&str>,
+        input: &ConversionInput,
+        conversion: &str,
+        exclusions: &Exclusions,
+        external_id: &ExternalId,
     ) -> anyhow::Result<Self> {
+        exclusions.check_user_agent(&input.user_agent)?;
+        exclusions.check_ip(&input.client_ip)?;
+
         // Default LinkedIn event
 
         let mut linkedin_event = LinkedinEvent {
-            conversion: event_name.to_string(),
-            event_time: edgee_event.timestamp_millis,
-            event_id: event_id.to_string(),
-            user_data: UserData::default(),
+            conversion: conversion.to_string(),
+            event_time: input.timestamp_millis,
+            event_id: input.event_id.clone(),
+            ..LinkedinEvent::default()
         };
 
         let mut user_data = UserData {
             ..UserData::default()
         };
 
-        let user_properties = edgee_event.context.user.properties.clone();
-        user_data
-            .external_ids
-            .push(edgee_event.context.user.user_id.clone());
-
-        user_data.user_ids.push(UserId {
-            id_type: "LINKEDIN_FIRST_PARTY_ADS_TRACKING_UUID".to_owned(),
-            id_value: li_fat_id.unwrap_or("").to_string(),
-        });
-
-        for (key, value) in user_properties.iter() {
-            match key.as_str() {
-                "email" => user_data.user_ids.push(UserId {
-                    id_type: "SHA256_EMAIL".to_owned(),
-                    id_value: hash_value(value),
-                }),
-                _ => {
-                    // do nothing
-                }
-            }
+        user_data.external_ids.extend(external_id.resolve(input));
+
+        if let Some(li_fat_id) = input.li_fat_id.as_ref().filter(|id| !id.is_empty()) {
+            user_data.user_ids.push(UserId {
+                id_type: "LINKEDIN_FIRST_PARTY_ADS_TRACKING_UUID".to_owned(),
+                id_value: li_fat_id.clone(),
+            });
+        }
+
+        if let Some(email) = input
+            .email
+            .as_deref()
+            .filter(|email| !email.trim().is_empty())
+        {
+            exclusions.check_email(email)?;
+            user_data.user_ids.push(UserId {
+                id_type: "SHA256_EMAIL".to_owned(),
+                id_value: hash_value(email),
+            })
         }
 
-        if edgee_event.consent.is_some() && edgee_event.consent.unwrap() != Consent::Granted {
+        if input
+            .consent
+            .is_some_and(|consent| consent != Consent::Granted)
+        {
             // Consent is not granted, so we don't send the event
             return Err(anyhow!("Consent is not granted"));
         }
@@ -130,7 +484,7 @@ impl LinkedinEvent {
 /// SHA256 hash value
 ///
 /// This function is used to hash the value.
-pub(crate) fn hash_value(input: &str) -> String {
+pub fn hash_value(input: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(input.as_bytes());
     format!("{:x}", hasher.finalize())
@@ -139,6 +493,8 @@ pub(crate) fn hash_value(input: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::products::ProductError;
+    use proptest::prelude::*;
 
     #[test]
     fn test_hash_value() {
@@ -154,6 +510,88 @@ mod tests {
         assert_eq!(hash_value(input), expected);
     }
 
+    #[test]
+    fn test_linkedin_event_skips_empty_identifiers() {
+        let input = ConversionInput {
+            user_id: " ".to_string(),
+            email: Some("john@example.com".to_string()),
+            li_fat_id: Some(String::new()),
+            ..ConversionInput::default()
+        };
+        let event = LinkedinEvent::new(
+            &input,
+            "conversion",
+            &Exclusions::default(),
+            &ExternalId::default(),
+        )
+        .unwrap();
+        assert!(event.user_data.external_ids.is_empty());
+        assert_eq!(event.user_data.user_ids.len(), 1);
+        assert_eq!(event.user_data.user_ids[0].id_type, "SHA256_EMAIL");
+
+        let input = ConversionInput {
+            email: Some(" ".to_string()),
+            ..input
+        };
+        assert!(LinkedinEvent::new(
+            &input,
+            "conversion",
+            &Exclusions::default(),
+            &ExternalId::default(),
+        )
+        .is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn prop_hash_value_is_lowercase_hex(input in any::<String>()) {
+            let hash = hash_value(&input);
+            prop_assert_eq!(hash.len(), 64);
+            prop_assert!(hash.bytes().all(|c| matches!(c, b'0'..=b'9' | b'a'..=b'f')));
+        }
+
+        #[test]
+        fn prop_linkedin_event_never_emits_empty_identifiers(
+            user_id in "[ a-z0-9_-]{0,8}",
+            email in proptest::option::of("[ a-z0-9@.+]{0,16}"),
+            li_fat_id in proptest::option::of("[a-f0-9-]{0,36}"),
+        ) {
+            let input = ConversionInput {
+                user_id,
+                email,
+                li_fat_id,
+                ..ConversionInput::default()
+            };
+            if let Ok(event) = LinkedinEvent::new(
+                &input,
+                "conversion",
+                &Exclusions::default(),
+                &ExternalId::default(),
+            ) {
+                prop_assert!(!event.user_data.user_ids.is_empty());
+                prop_assert!(event.user_data.external_ids.iter().all(|id| !id.trim().is_empty()));
+                prop_assert!(event.user_data.user_ids.iter().all(|id| !id.id_value.is_empty()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_linkedin_payload_external_id_settings() {
+        let settings = |key: &str, value: &str| {
+            vec![
+                (
+                    "linkedin_access_token".to_string(),
+                    "test_token".to_string(),
+                ),
+                (key.to_string(), value.to_string()),
+            ]
+        };
+
+        assert!(LinkedinPayload::new(settings("linkedin_external_id_source", "edgee_id")).is_ok());
+        assert!(LinkedinPayload::new(settings("linkedin_external_id_source", "email")).is_err());
+        assert!(LinkedinPayload::new(settings("linkedin_external_id_salt", "")).is_err());
+    }
+
     #[test]
     fn test_linkedin_payload_new_success() {
         let settings = vec![(
@@ -177,6 +615,393 @@ mod tests {
             .contains("Missing LinkedIn Access Token"));
     }
 
+    #[test]
+    fn test_parse_map_setting() {
+        let map = parse_map_setting(
+            "SKU-1=urn:lla:llaPartnerConversion:1, SKU-2 = urn:lla:llaPartnerConversion:2,",
+        )
+        .unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["SKU-1"], "urn:lla:llaPartnerConversion:1");
+        assert_eq!(map["SKU-2"], "urn:lla:llaPartnerConversion:2");
+
+        assert!(parse_map_setting("").unwrap().is_empty());
+        assert!(parse_map_setting("SKU-1").is_err());
+        assert!(parse_map_setting("=urn").is_err());
+    }
+
+    fn sample_event(event_id: &str) -> LinkedinEvent {
+        LinkedinEvent {
+            conversion: "urn:lla:llaPartnerConversion:123".to_string(),
+            event_time: 123,
+            event_id: event_id.to_string(),
+            ..LinkedinEvent::default()
+        }
+    }
+
+    fn sample_input(currency: Option<&str>, value: Option<&str>) -> ConversionInput {
+        let mut properties = vec![];
+        if let Some(currency) = currency {
+            properties.push(("currency".to_string(), currency.to_string()));
+        }
+        if let Some(value) = value {
+            properties.push(("value".to_string(), value.to_string()));
+        }
+        ConversionInput {
+            conversion: "urn:lla:llaPartnerConversion:123".to_string(),
+            properties,
+            products: vec![
+                vec![
+                    ("sku".to_string(), "SKU-1".to_string()),
+                    ("price".to_string(), "10.5".to_string()),
+                    ("quantity".to_string(), "2".to_string()),
+                ],
+                vec![
+                    ("sku".to_string(), "SKU-2".to_string()),
+                    ("price".to_string(), "4".to_string()),
+                    ("quantity".to_string(), "1".to_string()),
+                ],
+            ],
+            ..ConversionInput::default()
+        }
+    }
+
+    fn sample_converter() -> CurrencyConverter {
+        let rates = HashMap::from([("USD".to_string(), "0.92".to_string())]);
+        CurrencyConverter::new("EUR", &rates).unwrap()
+    }
+
+    #[test]
+    fn test_add_track_event_without_product_settings() {
+        let mut payload = LinkedinPayload::default();
+        let input = sample_input(Some("USD"), None);
+        payload
+            .add_track_event(sample_event("1"), &input.properties, &input.products)
+            .unwrap();
+        assert_eq!(payload.events.len(), 1);
+        assert_eq!(payload.events[0].conversion_value, None);
+        assert!(!payload.is_batch());
+    }
+
+    #[test]
+    fn test_add_track_event_invalid_value() {
+        for (currency, value, warning) in [
+            (
+                Some("USD"),
+                "abc",
+                "invalid_conversion_value: conversion value abc is not a number, sent without value",
+            ),
+            (
+                None,
+                "49.9",
+                "missing_conversion_currency: conversion value 49.9 has no currency, sent without value",
+            ),
+        ] {
+            let mut payload = LinkedinPayload::default();
+            let input = sample_input(currency, Some(value));
+            payload
+                .add_track_event(sample_event("1"), &input.properties, &[])
+                .unwrap();
+            assert_eq!(payload.events.len(), 1);
+            assert_eq!(payload.events[0].conversion_value, None);
+            assert_eq!(payload.warnings, vec![warning.to_string()]);
+        }
+    }
+
+    #[test]
+    fn test_add_track_event_value_from_products() {
+        let mut payload = LinkedinPayload {
+            value_from_products: true,
+            ..LinkedinPayload::default()
+        };
+        let input = sample_input(Some("usd"), None);
+        payload
+            .add_track_event(sample_event("1"), &input.properties, &input.products)
+            .unwrap();
+        assert_eq!(payload.events.len(), 1);
+        assert_eq!(
+            payload.events[0].conversion_value,
+            Some(ConversionValue {
+                currency_code: "USD".to_string(),
+                amount: "25.00".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_add_track_event_product_conversions() {
+        let mut payload = LinkedinPayload {
+            product_conversions: HashMap::from([(
+                "SKU-1".to_string(),
+                "urn:lla:llaPartnerConversion:1".to_string(),
+            )]),
+            ..LinkedinPayload::default()
+        };
+        let input = sample_input(Some("EUR"), None);
+        payload
+            .add_track_event(sample_event("1"), &input.properties, &input.products)
+            .unwrap();
+        assert!(payload.is_batch());
+        assert_eq!(payload.events.len(), 2);
+        assert_eq!(
+            payload.events[1].conversion,
+            "urn:lla:llaPartnerConversion:1"
+        );
+        assert_eq!(payload.events[1].event_id, "1-SKU-1");
+        assert_eq!(
+            payload.events[1].conversion_value,
+            Some(ConversionValue {
+                currency_code: "EUR".to_string(),
+                amount: "21.00".to_string(),
+            })
+        );
+
+        let body: serde_json::Value = serde_json::from_str(&payload.body().unwrap()).unwrap();
+        assert_eq!(body["elements"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_add_track_event_invalid_products() {
+        let mut payload = LinkedinPayload {
+            value_from_products: true,
+            ..LinkedinPayload::default()
+        };
+        let mut input = sample_input(Some("USD"), None);
+        input.products[1].retain(|(key, _)| key != "price");
+        let result = payload.add_track_event(sample_event("1"), &input.properties, &input.products);
+        let error = result.unwrap_err();
+        let product_error = error.downcast_ref::<ProductError>().unwrap();
+        assert_eq!(product_error.products[0].index, 1);
+        assert!(payload.events.is_empty());
+    }
+
+    #[test]
+    fn test_add_track_event_products_without_currency() {
+        let mut payload = LinkedinPayload {
+            value_from_products: true,
+            ..LinkedinPayload::default()
+        };
+        let input = sample_input(None, None);
+        payload
+            .add_track_event(sample_event("1"), &input.properties, &input.products)
+            .unwrap();
+        assert_eq!(payload.events.len(), 1);
+        assert_eq!(payload.events[0].conversion_value, None);
+        assert_eq!(
+            payload.warnings,
+            vec![
+                "missing_conversion_currency: products have no currency, sent without value"
+                    .to_string()
+            ]
+        );
+
+        let mut payload = LinkedinPayload {
+            product_conversions: HashMap::from([(
+                "SKU-1".to_string(),
+                "urn:lla:llaPartnerConversion:1".to_string(),
+            )]),
+            ..LinkedinPayload::default()
+        };
+        payload
+            .add_track_event(sample_event("1"), &input.properties, &input.products)
+            .unwrap();
+        assert_eq!(payload.events.len(), 2);
+        assert_eq!(payload.events[1].event_id, "1-SKU-1");
+        assert_eq!(payload.events[1].conversion_value, None);
+        assert_eq!(payload.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_add_track_event_value_with_currency_converter() {
+        let mut payload = LinkedinPayload {
+            currency_converter: Some(sample_converter()),
+            ..LinkedinPayload::default()
+        };
+        let mut input = sample_input(Some("USD"), Some("19.99"));
+        input.products.clear();
+        payload
+            .add_track_event(sample_event("1"), &input.properties, &input.products)
+            .unwrap();
+        assert_eq!(
+            payload.events[0].conversion_value,
+            Some(ConversionValue {
+                currency_code: "EUR".to_string(),
+                amount: "18.39".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_add_track_event_products_with_currency_converter() {
+        let mut payload = LinkedinPayload {
+            value_from_products: true,
+            currency_converter: Some(sample_converter()),
+            ..LinkedinPayload::default()
+        };
+        let input = sample_input(Some("USD"), None);
+        payload
+            .add_track_event(sample_event("1"), &input.properties, &input.products)
+            .unwrap();
+        // 25 USD * 0.92
+        assert_eq!(
+            payload.events[0].conversion_value,
+            Some(ConversionValue {
+                currency_code: "EUR".to_string(),
+                amount: "23.00".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_add_track_event_unknown_currency() {
+        let mut payload = LinkedinPayload::default();
+        let input = sample_input(Some("ABC"), Some("10"));
+        payload
+            .add_track_event(sample_event("1"), &input.properties, &input.products)
+            .unwrap();
+        assert_eq!(payload.events[0].conversion_value, None);
+        assert_eq!(
+            payload.warnings,
+            vec![
+                "invalid_conversion_currency: Unknown currency: ABC, sent without value"
+                    .to_string()
+            ]
+        );
+
+        let mut payload = LinkedinPayload {
+            value_from_products: true,
+            ..LinkedinPayload::default()
+        };
+        let input = sample_input(Some("ABC"), None);
+        payload
+            .add_track_event(sample_event("1"), &input.properties, &input.products)
+            .unwrap();
+        assert_eq!(payload.events[0].conversion_value, None);
+        assert_eq!(payload.warnings.len(), 1);
+
+        let mut payload = LinkedinPayload {
+            currency_converter: Some(sample_converter()),
+            ..LinkedinPayload::default()
+        };
+        let input = sample_input(Some("GBP"), Some("10"));
+        payload
+            .add_track_event(sample_event("1"), &input.properties, &input.products)
+            .unwrap();
+        assert_eq!(payload.events[0].conversion_value, None);
+        assert_eq!(
+            payload.warnings,
+            vec![
+                "invalid_conversion_currency: Missing currency rate from GBP to EUR, sent without value"
+                    .to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_linkedin_payload_new_oauth_settings() {
+        let mut settings = vec![
+            (
+                "linkedin_access_token".to_string(),
+                "test_token".to_string(),
+            ),
+            ("linkedin_client_id".to_string(), "client".to_string()),
+            ("linkedin_client_secret".to_string(), "secret".to_string()),
+        ];
+        assert!(LinkedinPayload::new(settings.clone()).is_err());
+
+        settings.push(("linkedin_refresh_token".to_string(), "refresh".to_string()));
+        assert!(LinkedinPayload::new(settings).is_ok());
+    }
+
+    #[test]
+    fn test_linkedin_payload_new_token_expiry() {
+        let settings = |expires_at: &str| {
+            vec![
+                (
+                    "linkedin_access_token".to_string(),
+                    "test_token".to_string(),
+                ),
+                (
+                    "linkedin_access_token_expires_at".to_string(),
+                    expires_at.to_string(),
+                ),
+            ]
+        };
+
+        let error = LinkedinPayload::new(settings("2020-01-01")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "token_expired: access token expired on 2020-01-01"
+        );
+
+        let payload = LinkedinPayload::new(settings("2999-01-01")).unwrap();
+        assert!(payload.warnings.is_empty());
+
+        let expires_soon = now_millis() + 86_400_000;
+        let payload = LinkedinPayload::new(settings(&expires_soon.to_string())).unwrap();
+        assert!(payload.warnings[0].starts_with("token_expiring_soon: "));
+
+        assert!(LinkedinPayload::new(settings("next week")).is_err());
+
+        for days in ["-1", "a week"] {
+            let mut settings = settings("2999-01-01");
+            settings.push((
+                "linkedin_access_token_warning_days".to_string(),
+                days.to_string(),
+            ));
+            assert_eq!(
+                LinkedinPayload::new(settings).unwrap_err().to_string(),
+                format!("Invalid linkedin_access_token_warning_days setting: {days}")
+            );
+        }
+    }
+
+    #[test]
+    fn test_linkedin_payload_new_currency_settings() {
+        let settings = vec![
+            (
+                "linkedin_access_token".to_string(),
+                "test_token".to_string(),
+            ),
+            ("linkedin_currency".to_string(), "eur".to_string()),
+            (
+                "linkedin_currency_rates".to_string(),
+                "USD=0.92,GBP=1.17".to_string(),
+            ),
+        ];
+        let payload = LinkedinPayload::new(settings).unwrap();
+        let converter = payload.currency_converter.unwrap();
+        assert_eq!(converter.target, "EUR");
+        assert_eq!(converter.rates.len(), 2);
+
+        let settings = vec![
+            (
+                "linkedin_access_token".to_string(),
+                "test_token".to_string(),
+            ),
+            (
+                "linkedin_currency_rates".to_string(),
+                "USD=0.92".to_string(),
+            ),
+        ];
+        assert!(LinkedinPayload::new(settings).is_err());
+    }
+
+    #[test]
+    fn test_batch_body() {
+        let payload = LinkedinPayload {
+            events: vec![sample_event("1")],
+            ..LinkedinPayload::default()
+        };
+        assert!(!payload.is_batch());
+
+        let body: serde_json::Value = serde_json::from_str(&payload.body().unwrap()).unwrap();
+        assert_eq!(body["eventId"], "1");
+
+        let body: serde_json::Value = serde_json::from_str(&payload.batch_body().unwrap()).unwrap();
+        assert_eq!(body["elements"][0]["eventId"], "1");
+    }
+
     #[test]
     fn test_user_id_creation() {
         let user_id = UserId {
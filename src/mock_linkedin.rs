@@ -0,0 +1,383 @@
+//! In-process stand-in for the LinkedIn `conversionEvents` API.
+//!
+//! The mock server checks the versioning and authentication headers of the requests,
+//! validates their body against the vendored schema, and answers with scripted responses,
+//! so requests built by the component can be tested end to end, by setting the `api_base_url`
+//! of the payload to the mock.
+
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::api_request::{LINKEDIN_VERSION, RESTLI_PROTOCOL_VERSION};
+use crate::exports::edgee::components::data_collection::{EdgeeRequest, HttpMethod};
+use crate::payload_schema::validate_payload;
+
+/// Scripted response of the mock server
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+impl MockResponse {
+    pub fn new(status: u16, body: &str) -> Self {
+        Self {
+            status,
+            body: body.to_string(),
+        }
+    }
+
+    /// Created event, the default response
+    pub fn created() -> Self {
+        Self::new(201, "")
+    }
+
+    fn error(status: u16, code: &str, message: &str) -> Self {
+        let body = serde_json::json!({ "status": status, "code": code, "message": message });
+        Self::new(status, &body.to_string())
+    }
+}
+
+/// Request received by the mock server
+#[derive(Debug, Clone)]
+pub struct ReceivedRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl ReceivedRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+pub struct MockLinkedin {
+    base_url: String,
+    access_token: String,
+    responses: Mutex<VecDeque<MockResponse>>,
+    requests: Mutex<Vec<ReceivedRequest>>,
+}
+
+impl MockLinkedin {
+    /// Start a mock server accepting the given access token, answering valid requests with
+    /// the scripted responses in order, then with `201 Created`
+    pub fn start(access_token: &str, responses: Vec<MockResponse>) -> Arc<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mock = Arc::new(Self {
+            base_url: format!("http://{}/rest", listener.local_addr().unwrap()),
+            access_token: access_token.to_string(),
+            responses: Mutex::new(responses.into()),
+            requests: Mutex::new(vec![]),
+        });
+
+        let server = Arc::clone(&mock);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                server.handle(stream);
+            }
+        });
+        mock
+    }
+
+    /// Base URL of the mock, to be set as the `api_base_url` of the payload
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn requests(&self) -> Vec<ReceivedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn handle(&self, mut stream: TcpStream) {
+        let Some(request) = read_request(&stream) else {
+            return;
+        };
+        let response = self.respond(&request);
+        self.requests.lock().unwrap().push(request);
+
+        let reason = match response.status {
+            200 => "OK",
+            201 => "Created",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            404 => "Not Found",
+            429 => "Too Many Requests",
+            _ => "Error",
+        };
+        let _ = write!(
+            stream,
+            "HTTP/1.1 {} {reason}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+            response.status,
+            response.body.len(),
+            response.body
+        );
+    }
+
+    fn respond(&self, request: &ReceivedRequest) -> MockResponse {
+        if request.method != "POST" || request.path != "/rest/conversionEvents" {
+            return MockResponse::error(404, "NOT_FOUND", "Unknown resource");
+        }
+        if request.header("Authorization") != Some(&format!("Bearer {}", self.access_token)) {
+            return MockResponse::error(401, "INVALID_ACCESS_TOKEN", "Invalid access token");
+        }
+        for (header, version) in [
+            ("LinkedIn-Version", LINKEDIN_VERSION),
+            ("X-Restli-Protocol-Version", RESTLI_PROTOCOL_VERSION),
+        ] {
+            if request.header(header) != Some(version) {
+                return MockResponse::error(
+                    400,
+                    "VERSION_MISSING",
+                    &format!("{header} header must be {version}"),
+                );
+            }
+        }
+
+        let errors = validate_payload(&request.body);
+        if !errors.is_empty() {
+            return MockResponse::error(400, "INVALID_CONVERSION_EVENT", &errors.join(", "));
+        }
+        let is_batch = request.body.trim_start().starts_with("{\"elements\"");
+        if is_batch != (request.header("X-RestLi-Method") == Some("BATCH_CREATE")) {
+            return MockResponse::error(
+                400,
+                "INVALID_RESTLI_METHOD",
+                "Batch bodies must be sent with X-RestLi-Method: BATCH_CREATE",
+            );
+        }
+
+        self.responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or_else(MockResponse::created)
+    }
+}
+
+fn read_request(stream: &TcpStream) -> Option<ReceivedRequest> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let path = parts.next()?.to_string();
+
+    let mut headers = vec![];
+    loop {
+        line.clear();
+        reader.read_line(&mut line).ok()?;
+        match line.trim_end().split_once(':') {
+            Some((key, value)) => headers.push((key.to_string(), value.trim().to_string())),
+            None => break,
+        }
+    }
+
+    let length = headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).ok()?;
+
+    Some(ReceivedRequest {
+        method,
+        path,
+        headers,
+        body: String::from_utf8(body).ok()?,
+    })
+}
+
+/// Send a request with a minimal HTTP/1.1 client, returning the status and body of the response
+pub fn send(request: &EdgeeRequest) -> (u16, String) {
+    let url = request.url.strip_prefix("http://").unwrap();
+    let (host, path) = url.split_at(url.find('/').unwrap());
+    let method = match request.method {
+        HttpMethod::Get => "GET",
+        HttpMethod::Put => "PUT",
+        HttpMethod::Post => "POST",
+        HttpMethod::Delete => "DELETE",
+    };
+
+    let mut stream = TcpStream::connect(host).unwrap();
+    let mut message = format!("{method} {path} HTTP/1.1\r\nhost: {host}\r\n");
+    for (key, value) in &request.headers {
+        message.push_str(&format!("{key}: {value}\r\n"));
+    }
+    message.push_str(&format!(
+        "content-length: {}\r\nconnection: close\r\n\r\n{}",
+        request.body.len(),
+        request.body
+    ));
+    stream.write_all(message.as_bytes()).unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+    (status, body.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build_edgee_request;
+    use crate::conversion::{build_conversion, Consent, ConversionInput};
+    use crate::response::{parse_response, ErrorClass};
+
+    const ACCESS_TOKEN: &str = "test_token";
+
+    fn sample_input() -> ConversionInput {
+        ConversionInput {
+            conversion: "urn:lla:llaPartnerConversion:123".to_string(),
+            timestamp_millis: 1714566600000,
+            event_id: "order-1".to_string(),
+            user_id: "user-123".to_string(),
+            email: Some("john@example.com".to_string()),
+            consent: Some(Consent::Granted),
+            properties: vec![
+                ("value".to_string(), "49.9".to_string()),
+                ("currency".to_string(), "USD".to_string()),
+            ],
+            ..ConversionInput::default()
+        }
+    }
+
+    fn build_request(
+        mock: &MockLinkedin,
+        input: &ConversionInput,
+        settings: &[(&str, &str)],
+    ) -> EdgeeRequest {
+        let mut settings: Vec<(String, String)> = settings
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        if !settings
+            .iter()
+            .any(|(key, _)| key == "linkedin_access_token")
+        {
+            settings.push((
+                "linkedin_access_token".to_string(),
+                ACCESS_TOKEN.to_string(),
+            ));
+        }
+        let mut payload = build_conversion(input, settings).unwrap();
+        payload.api_base_url = Some(mock.base_url().to_string());
+        build_edgee_request(payload).unwrap().0
+    }
+
+    #[test]
+    fn test_mock_single_event_created() {
+        let mock = MockLinkedin::start(ACCESS_TOKEN, vec![]);
+        let request = build_request(&mock, &sample_input(), &[]);
+
+        let (status, body) = send(&request);
+        assert_eq!(status, 201);
+        assert_eq!(parse_response(status, &body), Ok(()));
+
+        let received = mock.requests();
+        assert_eq!(received.len(), 1);
+        assert_eq!(
+            received[0].header("LinkedIn-Version"),
+            Some(LINKEDIN_VERSION)
+        );
+        assert_eq!(received[0].body, request.body);
+    }
+
+    #[test]
+    fn test_mock_batch_partial_failure() {
+        let mock = MockLinkedin::start(
+            ACCESS_TOKEN,
+            vec![MockResponse::new(
+                200,
+                include_str!("../fixtures/responses/batch_partial_failure.json"),
+            )],
+        );
+        let input = ConversionInput {
+            products: vec![vec![
+                ("sku".to_string(), "SKU-1".to_string()),
+                ("price".to_string(), "10".to_string()),
+                ("quantity".to_string(), "1".to_string()),
+            ]],
+            ..sample_input()
+        };
+        let request = build_request(
+            &mock,
+            &input,
+            &[(
+                "linkedin_product_conversions",
+                "SKU-1=urn:lla:llaPartnerConversion:1",
+            )],
+        );
+
+        let (status, body) = send(&request);
+        assert_eq!(status, 200);
+        let error = parse_response(status, &body).unwrap_err();
+        assert_eq!(error.class, ErrorClass::InvalidPayload);
+        assert_eq!(error.element_errors.len(), 1);
+        assert_eq!(error.element_errors[0].index, 1);
+        assert_eq!(
+            mock.requests()[0].header("X-RestLi-Method"),
+            Some("BATCH_CREATE")
+        );
+    }
+
+    #[test]
+    fn test_mock_rate_limited_then_retried() {
+        let mock = MockLinkedin::start(
+            ACCESS_TOKEN,
+            vec![MockResponse::new(
+                429,
+                include_str!("../fixtures/responses/rate_limited.json"),
+            )],
+        );
+        let request = build_request(&mock, &sample_input(), &[]);
+
+        let (status, body) = send(&request);
+        let error = parse_response(status, &body).unwrap_err();
+        assert_eq!(error.class, ErrorClass::RateLimited);
+        assert!(error.class.is_retryable());
+
+        let (status, body) = send(&request);
+        assert_eq!(parse_response(status, &body), Ok(()));
+        assert_eq!(mock.requests().len(), 2);
+    }
+
+    #[test]
+    fn test_mock_invalid_access_token() {
+        let mock = MockLinkedin::start(ACCESS_TOKEN, vec![]);
+        let request = build_request(
+            &mock,
+            &sample_input(),
+            &[("linkedin_access_token", "revoked_token")],
+        );
+
+        let (status, body) = send(&request);
+        assert_eq!(status, 401);
+        let error = parse_response(status, &body).unwrap_err();
+        assert_eq!(error.class, ErrorClass::AuthExpired);
+        assert!(!error.class.is_retryable());
+    }
+
+    #[test]
+    fn test_mock_rejects_invalid_body() {
+        let mock = MockLinkedin::start(ACCESS_TOKEN, vec![]);
+        let mut request = build_request(&mock, &sample_input(), &[]);
+        request.body = request
+            .body
+            .replace("conversionHappenedAt", "conversion_happened_at");
+
+        let (status, body) = send(&request);
+        assert_eq!(status, 400);
+        let error = parse_response(status, &body).unwrap_err();
+        assert_eq!(error.class, ErrorClass::InvalidPayload);
+    }
+}
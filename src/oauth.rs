@@ -0,0 +1,308 @@
+use std::fmt;
+
+use anyhow::anyhow;
+use serde::Deserialize;
+
+use crate::api_request::{percent_encode, ApiRequest};
+use crate::exports::edgee::components::data_collection::HttpMethod;
+use crate::timestamp::format_date;
+
+/// LinkedIn OAuth 2.0 token endpoint
+pub const ACCESS_TOKEN_URL: &str = "https://www.linkedin.com/oauth/v2/accessToken";
+
+/// OAuth Client
+///
+/// Credentials of the LinkedIn app used to refresh the access token, before its 60 days expiry.
+/// To know more about refresh tokens, check the online documentation: https://learn.microsoft.com/en-us/linkedin/shared/authentication/programmatic-refresh-tokens
+#[derive(Clone, PartialEq)]
+pub struct OAuthClient {
+    pub client_id: String,
+    pub client_secret: String,
+    pub refresh_token: String,
+}
+
+// secrets are masked, so that they don't leak to the logs
+impl fmt::Debug for OAuthClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OAuthClient")
+            .field("client_id", &self.client_id)
+            .field("client_secret", &"***")
+            .field("refresh_token", &"***")
+            .finish()
+    }
+}
+
+impl OAuthClient {
+    /// OAuth client from the optional settings, which must be all set or all unset
+    pub fn new(
+        client_id: Option<&str>,
+        client_secret: Option<&str>,
+        refresh_token: Option<&str>,
+    ) -> anyhow::Result<Option<Self>> {
+        match (client_id, client_secret, refresh_token) {
+            (None, None, None) => Ok(None),
+            (Some(client_id), Some(client_secret), Some(refresh_token))
+                if !client_id.is_empty() && !client_secret.is_empty() && !refresh_token.is_empty() =>
+            {
+                Ok(Some(Self {
+                    client_id: client_id.to_string(),
+                    client_secret: client_secret.to_string(),
+                    refresh_token: refresh_token.to_string(),
+                }))
+            }
+            _ => Err(anyhow!(
+                "linkedin_client_id, linkedin_client_secret and linkedin_refresh_token must be set together"
+            )),
+        }
+    }
+
+    /// `POST /oauth/v2/accessToken` request exchanging the refresh token for a new access token
+    pub fn refresh_request(&self) -> ApiRequest {
+        let body = [
+            ("grant_type", "refresh_token"),
+            ("refresh_token", &self.refresh_token),
+            ("client_id", &self.client_id),
+            ("client_secret", &self.client_secret),
+        ]
+        .iter()
+        .map(|(key, value)| format!("{key}={}", percent_encode(value)))
+        .collect::<Vec<_>>()
+        .join("&");
+
+        ApiRequest {
+            method: HttpMethod::Post,
+            url: ACCESS_TOKEN_URL.to_string(),
+            headers: vec![(
+                String::from("content-type"),
+                String::from("application/x-www-form-urlencoded"),
+            )],
+            body,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: i64,
+    refresh_token: Option<String>,
+    refresh_token_expires_in: Option<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TokenError {
+    error: String,
+    error_description: Option<String>,
+}
+
+/// Access Token
+///
+/// Access token returned by a refresh request, with its expiry.
+#[derive(Clone, PartialEq)]
+pub struct AccessToken {
+    pub access_token: String,
+    /// Expiry of the access token, in milliseconds since the Unix epoch
+    pub expires_at: i64,
+    /// New refresh token, when LinkedIn rotated it
+    pub refresh_token: Option<String>,
+    /// Expiry of the refresh token, in milliseconds since the Unix epoch
+    pub refresh_token_expires_at: Option<i64>,
+}
+
+impl fmt::Debug for AccessToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccessToken")
+            .field("access_token", &"***")
+            .field("expires_at", &self.expires_at)
+            .field("refresh_token", &self.refresh_token.as_ref().map(|_| "***"))
+            .field("refresh_token_expires_at", &self.refresh_token_expires_at)
+            .finish()
+    }
+}
+
+/// Parse the response of a refresh request received at `now_millis`
+pub fn parse_token_response(
+    status: u16,
+    body: &str,
+    now_millis: i64,
+) -> anyhow::Result<AccessToken> {
+    if !(200..300).contains(&status) {
+        return Err(match serde_json::from_str::<TokenError>(body) {
+            Ok(error) => anyhow!(
+                "Token refresh failed ({status}): {}{}",
+                error.error,
+                error
+                    .error_description
+                    .map(|description| format!(", {description}"))
+                    .unwrap_or_default()
+            ),
+            Err(_) => anyhow!("Token refresh failed ({status})"),
+        });
+    }
+
+    let response: TokenResponse =
+        serde_json::from_str(body).map_err(|e| anyhow!("Invalid token refresh response: {e}"))?;
+    let expires_at = |seconds: i64| now_millis.saturating_add(seconds.saturating_mul(1000));
+
+    Ok(AccessToken {
+        access_token: response.access_token,
+        expires_at: expires_at(response.expires_in),
+        refresh_token: response.refresh_token,
+        refresh_token_expires_at: response.refresh_token_expires_in.map(expires_at),
+    })
+}
+
+/// Tokens expiring within this number of days are flagged with a warning
+pub const DEFAULT_EXPIRY_WARNING_DAYS: i64 = 7;
+
+/// Token Expiry
+///
+/// Known expiry of the access token, set with the `linkedin_access_token_expires_at` setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenExpiry {
+    /// Expiry of the access token, in milliseconds since the Unix epoch
+    pub expires_at: i64,
+    /// Number of days before the expiry when the token is flagged
+    pub warning_days: i64,
+}
+
+/// The access token is expired, requests would be rejected by LinkedIn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenExpired {
+    pub expires_at: i64,
+}
+
+impl fmt::Display for TokenExpired {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "token_expired: access token expired on {}",
+            format_date(self.expires_at)
+        )
+    }
+}
+
+impl std::error::Error for TokenExpired {}
+
+impl TokenExpiry {
+    /// Check the token at `now_millis`, returning a warning when it expires soon
+    pub fn check(&self, now_millis: i64) -> Result<Option<String>, TokenExpired> {
+        if self.expires_at <= now_millis {
+            return Err(TokenExpired {
+                expires_at: self.expires_at,
+            });
+        }
+
+        let warning_millis = self.warning_days.saturating_mul(86_400_000);
+        if self.expires_at - now_millis <= warning_millis {
+            return Ok(Some(format!(
+                "token_expiring_soon: access token expires on {}, refresh it before conversions are rejected",
+                format_date(self.expires_at)
+            )));
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_client() -> OAuthClient {
+        OAuthClient::new(Some("client"), Some("s3cr3t/+="), Some("refresh"))
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_oauth_client_new() {
+        assert_eq!(OAuthClient::new(None, None, None).unwrap(), None);
+        assert!(OAuthClient::new(Some("client"), None, Some("refresh")).is_err());
+        assert!(OAuthClient::new(Some("client"), Some(""), Some("refresh")).is_err());
+    }
+
+    #[test]
+    fn test_oauth_client_debug() {
+        let debug = format!("{:?}", sample_client());
+        assert_eq!(
+            debug,
+            r#"OAuthClient { client_id: "client", client_secret: "***", refresh_token: "***" }"#
+        );
+
+        let token = parse_token_response(
+            200,
+            r#"{"access_token":"new-token","expires_in":60,"refresh_token":"new-refresh"}"#,
+            0,
+        )
+        .unwrap();
+        let debug = format!("{token:?}");
+        assert!(!debug.contains("new-token") && !debug.contains("new-refresh"));
+    }
+
+    #[test]
+    fn test_refresh_request() {
+        let request = sample_client().refresh_request();
+        assert_eq!(request.method, HttpMethod::Post);
+        assert_eq!(request.url, "https://www.linkedin.com/oauth/v2/accessToken");
+        assert_eq!(
+            request.body,
+            "grant_type=refresh_token&refresh_token=refresh&client_id=client&client_secret=s3cr3t%2F%2B%3D"
+        );
+    }
+
+    #[test]
+    fn test_parse_token_response() {
+        let body = r#"{"access_token":"new-token","expires_in":5184000,"refresh_token":"new-refresh","refresh_token_expires_in":31536000,"scope":"rw_conversions"}"#;
+        let token = parse_token_response(200, body, 1_000).unwrap();
+        assert_eq!(
+            token,
+            AccessToken {
+                access_token: "new-token".to_string(),
+                expires_at: 5_184_001_000,
+                refresh_token: Some("new-refresh".to_string()),
+                refresh_token_expires_at: Some(31_536_001_000),
+            }
+        );
+
+        let body = r#"{"access_token":"new-token","expires_in":60}"#;
+        let token = parse_token_response(200, body, 0).unwrap();
+        assert_eq!(token.expires_at, 60_000);
+        assert_eq!(token.refresh_token, None);
+    }
+
+    #[test]
+    fn test_parse_token_response_error() {
+        let body = r#"{"error":"invalid_grant","error_description":"The provided authorization grant or refresh token is invalid, expired or revoked"}"#;
+        let error = parse_token_response(400, body, 0).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Token refresh failed (400): invalid_grant, The provided authorization grant or refresh token is invalid, expired or revoked"
+        );
+
+        assert!(parse_token_response(500, "", 0).is_err());
+        assert!(parse_token_response(200, "{}", 0).is_err());
+    }
+
+    #[test]
+    fn test_token_expiry() {
+        // 2024-05-01
+        let expiry = TokenExpiry {
+            expires_at: 1714521600000,
+            warning_days: 7,
+        };
+        let day = 86_400_000;
+
+        assert_eq!(expiry.check(expiry.expires_at - 30 * day), Ok(None));
+        assert_eq!(
+            expiry.check(expiry.expires_at - 2 * day),
+            Ok(Some("token_expiring_soon: access token expires on 2024-05-01, refresh it before conversions are rejected".to_string()))
+        );
+
+        let error = expiry.check(expiry.expires_at).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "token_expired: access token expired on 2024-05-01"
+        );
+    }
+}
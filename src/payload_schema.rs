@@ -0,0 +1,98 @@
+//! JSON schema of the LinkedIn CAPI `conversionEvents` body.
+//!
+//! The schema is vendored in `schemas/conversion_events.schema.json`, and every payload built in tests
+//! is validated against it, so renaming a field of `LinkedinEvent` or `UserData` breaks the tests
+//! instead of silently breaking the API calls.
+
+use std::sync::OnceLock;
+
+use jsonschema::Validator;
+use serde_json::Value;
+
+const SCHEMA: &str = include_str!("../schemas/conversion_events.schema.json");
+
+fn validator() -> &'static Validator {
+    static VALIDATOR: OnceLock<Validator> = OnceLock::new();
+    VALIDATOR.get_or_init(|| {
+        let schema: Value = serde_json::from_str(SCHEMA).expect("invalid schema JSON");
+        jsonschema::validator_for(&schema).expect("invalid JSON schema")
+    })
+}
+
+/// Errors of a request body against the schema
+pub fn validate_payload(body: &str) -> Vec<String> {
+    let body: Value = match serde_json::from_str(body) {
+        Ok(body) => body,
+        Err(error) => return vec![format!("invalid JSON: {error}")],
+    };
+    validator()
+        .iter_errors(&body)
+        .map(|error| format!("{}: {error}", error.instance_path))
+        .collect()
+}
+
+/// Assert that a request body matches the schema of LinkedIn CAPI
+#[track_caller]
+pub fn assert_valid_payload(body: &str) {
+    let errors = validate_payload(body);
+    assert!(
+        errors.is_empty(),
+        "payload does not match the conversionEvents schema:\n{}\n{body}",
+        errors.join("\n")
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linkedin_payload::{LinkedinEvent, UserData, UserId};
+
+    fn sample_event() -> LinkedinEvent {
+        LinkedinEvent {
+            conversion: "urn:lla:llaPartnerConversion:123".to_string(),
+            event_time: 1714566600000,
+            event_id: "event-1".to_string(),
+            user_data: UserData {
+                user_ids: vec![UserId {
+                    id_type: "SHA256_EMAIL".to_string(),
+                    id_value: "973dfe463ec85785f5f95af5ba3906eedb2d931c24e69824a89ea65dba4e813b"
+                        .to_string(),
+                }],
+                external_ids: vec!["user-1".to_string()],
+            },
+            ..LinkedinEvent::default()
+        }
+    }
+
+    #[test]
+    fn test_linkedin_event_matches_schema() {
+        let event = serde_json::to_value(sample_event()).unwrap();
+        assert_valid_payload(&event.to_string());
+        assert_valid_payload(&serde_json::json!({ "elements": [event] }).to_string());
+    }
+
+    #[test]
+    fn test_schema_rejects_invalid_payloads() {
+        let event = serde_json::to_value(sample_event()).unwrap();
+
+        let mut renamed = event.clone();
+        let time = renamed
+            .as_object_mut()
+            .unwrap()
+            .remove("conversionHappenedAt")
+            .unwrap();
+        renamed["conversion_happened_at"] = time;
+        assert!(!validate_payload(&renamed.to_string()).is_empty());
+
+        let mut unhashed = event.clone();
+        unhashed["user"]["userIds"][0]["idValue"] = "john@example.com".into();
+        assert!(!validate_payload(&unhashed.to_string()).is_empty());
+
+        let mut without_ids = event;
+        without_ids["user"]["userIds"] = serde_json::json!([]);
+        assert!(!validate_payload(&without_ids.to_string()).is_empty());
+
+        assert!(!validate_payload(r#"{"elements": []}"#).is_empty());
+        assert!(!validate_payload("not json").is_empty());
+    }
+}
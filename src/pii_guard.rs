@@ -0,0 +1,333 @@
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use serde_json::Value;
+
+use crate::linkedin_payload::hash_value;
+
+/// PII Guard
+///
+/// Final check of the body sent to LinkedIn, looking for raw emails, phone numbers and IP addresses
+/// in fields that are not hashed, ex: a plain email mapped to `externalIds` or `eventId`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PiiGuard {
+    /// Bodies with PII are not sent
+    Reject,
+    /// PII values are replaced with their SHA256 hash
+    #[default]
+    Redact,
+    /// Bodies are not checked
+    Off,
+}
+
+impl FromStr for PiiGuard {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim() {
+            "reject" => Ok(PiiGuard::Reject),
+            "redact" => Ok(PiiGuard::Redact),
+            "off" => Ok(PiiGuard::Off),
+            _ => Err(anyhow!(
+                "Invalid PII guard {value}, expected reject, redact or off"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PiiKind {
+    Email,
+    Phone,
+    Ip,
+}
+
+impl fmt::Display for PiiKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PiiKind::Email => write!(f, "email"),
+            PiiKind::Phone => write!(f, "phone number"),
+            PiiKind::Ip => write!(f, "IP address"),
+        }
+    }
+}
+
+/// PII value found in a field of the body, ex: `user.externalIds[0]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PiiFinding {
+    pub kind: PiiKind,
+    pub path: String,
+}
+
+impl fmt::Display for PiiFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} in {}", self.kind, self.path)
+    }
+}
+
+/// Body refused because it contains PII. The values themselves are not part of the message,
+/// so that they don't leak to the logs either.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PiiDetected(pub Vec<PiiFinding>);
+
+impl fmt::Display for PiiDetected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "pii_detected: ")?;
+        for (i, finding) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{finding}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for PiiDetected {}
+
+impl PiiGuard {
+    /// Check a JSON body, returning it with the PII values redacted in the `redact` mode,
+    /// along with the findings
+    pub fn apply(&self, body: String) -> Result<(String, Vec<PiiFinding>), PiiDetected> {
+        if *self == PiiGuard::Off {
+            return Ok((body, vec![]));
+        }
+        let Ok(mut value) = serde_json::from_str::<Value>(&body) else {
+            return Ok((body, vec![]));
+        };
+
+        let mut findings = vec![];
+        scan(
+            &mut value,
+            String::new(),
+            *self == PiiGuard::Redact,
+            &mut findings,
+        );
+        match (findings.is_empty(), self) {
+            (true, _) => Ok((body, findings)),
+            (false, PiiGuard::Redact) => Ok((value.to_string(), findings)),
+            (false, _) => Err(PiiDetected(findings)),
+        }
+    }
+}
+
+fn scan(value: &mut Value, path: String, redact: bool, findings: &mut Vec<PiiFinding>) {
+    match value {
+        Value::String(string) => {
+            if let Some(kind) = detect(string) {
+                if redact {
+                    *string = hash_value(string);
+                }
+                findings.push(PiiFinding { kind, path });
+            }
+        }
+        Value::Array(values) => {
+            for (i, value) in values.iter_mut().enumerate() {
+                scan(value, format!("{path}[{i}]"), redact, findings);
+            }
+        }
+        Value::Object(object) => {
+            // hashed emails are what LinkedIn expects
+            if object.get("idType").and_then(Value::as_str) == Some("SHA256_EMAIL") {
+                return;
+            }
+            for (key, value) in object.iter_mut() {
+                let path = match path.as_str() {
+                    "" => key.clone(),
+                    path => format!("{path}.{key}"),
+                };
+                scan(value, path, redact, findings);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Kind of PII the value looks like, if any
+pub fn detect(value: &str) -> Option<PiiKind> {
+    if is_phone(value) {
+        return Some(PiiKind::Phone);
+    }
+    let tokens = value.split(|c: char| c.is_whitespace() || ",;<>()[]\"'&?=/|".contains(c));
+    for token in tokens {
+        if is_email(token) {
+            return Some(PiiKind::Email);
+        }
+        if is_ip(token) {
+            return Some(PiiKind::Ip);
+        }
+    }
+    None
+}
+
+/// IPv4 addresses, or IPv6 addresses with at least 3 groups: short hex ids such as `cafe::1` are valid IPv6
+/// addresses too, but are rarely client addresses.
+fn is_ip(token: &str) -> bool {
+    match token.parse::<IpAddr>() {
+        Ok(IpAddr::V4(_)) => true,
+        Ok(IpAddr::V6(_)) => token.split(':').filter(|group| !group.is_empty()).count() >= 3,
+        Err(_) => false,
+    }
+}
+
+fn is_email(token: &str) -> bool {
+    let Some((local, domain)) = token.rsplit_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && domain.contains('.')
+        && domain.split('.').all(|label| {
+            !label.is_empty() && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+        })
+}
+
+/// International numbers (`+33 6 12 34 56 78`), or national numbers with an area code in parentheses (`(555) 123-4567`).
+/// Other digits are not considered phone numbers, as dashed or dotted digits are usually internal ids or dates,
+/// ex: `20240501-123456`.
+fn is_phone(value: &str) -> bool {
+    let value = value.trim();
+    let (international, number) = match value.strip_prefix('+') {
+        Some(number) => (true, number),
+        None => (false, value),
+    };
+    if !number
+        .chars()
+        .all(|c| c.is_ascii_digit() || " -.()".contains(c))
+    {
+        return false;
+    }
+
+    let digits = number.chars().filter(char::is_ascii_digit).count();
+    if international {
+        (8..=15).contains(&digits)
+    } else {
+        number.contains('(') && (10..=15).contains(&digits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_detect() {
+        for (value, expected) in [
+            ("john@example.com", Some(PiiKind::Email)),
+            (
+                "order for John.Doe+ads@mail.example.co.uk",
+                Some(PiiKind::Email),
+            ),
+            ("+33 6 12 34 56 78", Some(PiiKind::Phone)),
+            ("+393331231231", Some(PiiKind::Phone)),
+            ("(555) 123-4567", Some(PiiKind::Phone)),
+            ("81.2.69.142", Some(PiiKind::Ip)),
+            ("client 2001:db8::1", Some(PiiKind::Ip)),
+            ("user-123", None),
+            ("1234567890", None),
+            // ids and dates
+            ("555.123.4567", None),
+            ("20240501-123456", None),
+            ("2024-0501-1234", None),
+            ("order 2024-0501-1234", None),
+            ("cafe::1", None),
+            ("dead::beef", None),
+            ("2024-05-01", None),
+            ("49.90", None),
+            ("3f1c2b7e-8a4d-4e21-9c6b-5d0f7a9e1b23", None),
+            ("urn:lla:llaPartnerConversion:123", None),
+            ("@handle", None),
+            (
+                "855f96e983f1f8e8be944692b6f719fd54329826cb62e98015efee8e2e071dd4",
+                None,
+            ),
+        ] {
+            assert_eq!(detect(value), expected, "{value}");
+        }
+    }
+
+    #[test]
+    fn test_pii_guard_from_str() {
+        assert_eq!("reject".parse::<PiiGuard>().unwrap(), PiiGuard::Reject);
+        assert_eq!(" redact ".parse::<PiiGuard>().unwrap(), PiiGuard::Redact);
+        assert_eq!("off".parse::<PiiGuard>().unwrap(), PiiGuard::Off);
+        assert!("true".parse::<PiiGuard>().is_err());
+        assert_eq!(PiiGuard::default(), PiiGuard::Redact);
+    }
+
+    fn sample_body(external_id: &str) -> String {
+        serde_json::json!({
+            "conversion": "urn:lla:llaPartnerConversion:123",
+            "conversionHappenedAt": 1714566600000i64,
+            "eventId": "order-1",
+            "user": {
+                "externalIds": [external_id],
+                "userIds": [{
+                    "idType": "SHA256_EMAIL",
+                    "idValue": "855f96e983f1f8e8be944692b6f719fd54329826cb62e98015efee8e2e071dd4",
+                }],
+            },
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_pii_guard_without_pii() {
+        let body = sample_body("user-123");
+        assert_eq!(
+            PiiGuard::Reject.apply(body.clone()),
+            Ok((body.clone(), vec![]))
+        );
+        assert_eq!(PiiGuard::Redact.apply(body.clone()), Ok((body, vec![])));
+    }
+
+    #[test]
+    fn test_pii_guard_reject() {
+        let error = PiiGuard::Reject
+            .apply(sample_body("john@example.com"))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "pii_detected: email in user.externalIds[0]"
+        );
+    }
+
+    #[test]
+    fn test_pii_guard_batch() {
+        let body = format!(
+            r#"{{"elements": [{}, {}]}}"#,
+            sample_body("user-123"),
+            sample_body("+33 6 12 34 56 78")
+        );
+        let error = PiiGuard::Reject.apply(body).unwrap_err();
+        assert_eq!(
+            error.0,
+            vec![PiiFinding {
+                kind: PiiKind::Phone,
+                path: "elements[1].user.externalIds[0]".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_pii_guard_redact() {
+        let (body, findings) = PiiGuard::Redact
+            .apply(sample_body("john@example.com"))
+            .unwrap();
+        assert_eq!(findings.len(), 1);
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(
+            body["user"]["externalIds"][0],
+            hash_value("john@example.com")
+        );
+        assert_eq!(body["eventId"], "order-1");
+    }
+
+    #[test]
+    fn test_pii_guard_off() {
+        let body = sample_body("john@example.com");
+        assert_eq!(PiiGuard::Off.apply(body.clone()), Ok((body, vec![])));
+    }
+}
@@ -0,0 +1,224 @@
+use std::fmt;
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+
+use crate::exports::edgee::components::data_collection::Dict;
+
+/// Product
+///
+/// A product line of a `track` event, as sent in `TrackData.products`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Product {
+    pub sku: String,
+    pub price: Decimal,
+    pub quantity: Decimal,
+}
+
+impl Product {
+    pub fn value(&self) -> Decimal {
+        self.price.saturating_mul(self.quantity)
+    }
+}
+
+/// Reason why a product row could not be used.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProductIssue {
+    MissingPrice,
+    InvalidPrice(String),
+    MissingQuantity,
+    InvalidQuantity(String),
+}
+
+impl fmt::Display for ProductIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProductIssue::MissingPrice => write!(f, "missing price"),
+            ProductIssue::InvalidPrice(price) => write!(f, "invalid price '{price}'"),
+            ProductIssue::MissingQuantity => write!(f, "missing quantity"),
+            ProductIssue::InvalidQuantity(quantity) => write!(f, "invalid quantity '{quantity}'"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidProduct {
+    /// Position of the product in `TrackData.products`
+    pub index: usize,
+    pub sku: Option<String>,
+    pub issues: Vec<ProductIssue>,
+}
+
+/// Error returned when one or more product rows are missing a price or a quantity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProductError {
+    pub products: Vec<InvalidProduct>,
+}
+
+impl fmt::Display for ProductError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid products:")?;
+        for (i, product) in self.products.iter().enumerate() {
+            let separator = if i == 0 { " " } else { "; " };
+            write!(f, "{separator}#{}", product.index)?;
+            if let Some(sku) = &product.sku {
+                write!(f, " (sku {sku})")?;
+            }
+            let issues: Vec<String> = product.issues.iter().map(|i| i.to_string()).collect();
+            write!(f, ": {}", issues.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ProductError {}
+
+/// Parse the products of a `track` event.
+///
+/// Every product must have a numeric `price` and `quantity`. All invalid rows are reported
+/// at once in a `ProductError`.
+pub fn parse_products(products: &[Dict]) -> Result<Vec<Product>, ProductError> {
+    let mut parsed = vec![];
+    let mut invalid = vec![];
+
+    for (index, product) in products.iter().enumerate() {
+        let get = |name: &str| {
+            product
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.trim())
+                .filter(|value| !value.is_empty())
+        };
+
+        let sku = get("sku").or_else(|| get("product_id")).map(String::from);
+
+        let mut issues = vec![];
+        let price = match get("price") {
+            None => {
+                issues.push(ProductIssue::MissingPrice);
+                None
+            }
+            Some(price) => match Decimal::from_str(price) {
+                Ok(value) if !value.is_sign_negative() => Some(value),
+                _ => {
+                    issues.push(ProductIssue::InvalidPrice(price.to_string()));
+                    None
+                }
+            },
+        };
+        let quantity = match get("quantity") {
+            None => {
+                issues.push(ProductIssue::MissingQuantity);
+                None
+            }
+            Some(quantity) => match Decimal::from_str(quantity) {
+                Ok(value) if value.is_sign_positive() && !value.is_zero() => Some(value),
+                _ => {
+                    issues.push(ProductIssue::InvalidQuantity(quantity.to_string()));
+                    None
+                }
+            },
+        };
+
+        match (price, quantity) {
+            (Some(price), Some(quantity)) => parsed.push(Product {
+                sku: sku.unwrap_or_default(),
+                price,
+                quantity,
+            }),
+            _ => invalid.push(InvalidProduct { index, sku, issues }),
+        }
+    }
+
+    if invalid.is_empty() {
+        Ok(parsed)
+    } else {
+        Err(ProductError { products: invalid })
+    }
+}
+
+/// Total value of the products (sum of price * quantity)
+pub fn products_value(products: &[Product]) -> Decimal {
+    products.iter().fold(Decimal::ZERO, |total, product| {
+        total.saturating_add(product.value())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn product(fields: &[(&str, &str)]) -> Dict {
+        fields
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_products() {
+        let products = vec![
+            product(&[("sku", "SKU-1"), ("price", "10.5"), ("quantity", "2")]),
+            product(&[("product_id", "P-2"), ("price", "3"), ("quantity", "1")]),
+        ];
+
+        let parsed = parse_products(&products).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].sku, "SKU-1");
+        assert_eq!(parsed[1].sku, "P-2");
+        assert_eq!(products_value(&parsed), Decimal::from(24));
+    }
+
+    #[test]
+    fn test_parse_products_reports_invalid_rows() {
+        let products = vec![
+            product(&[("sku", "SKU-1"), ("price", "10.5"), ("quantity", "2")]),
+            product(&[("sku", "SKU-2"), ("quantity", "1")]),
+            product(&[("price", "abc")]),
+        ];
+
+        let error = parse_products(&products).unwrap_err();
+        assert_eq!(
+            error.products,
+            vec![
+                InvalidProduct {
+                    index: 1,
+                    sku: Some("SKU-2".to_string()),
+                    issues: vec![ProductIssue::MissingPrice],
+                },
+                InvalidProduct {
+                    index: 2,
+                    sku: None,
+                    issues: vec![
+                        ProductIssue::InvalidPrice("abc".to_string()),
+                        ProductIssue::MissingQuantity
+                    ],
+                },
+            ]
+        );
+        assert_eq!(
+            error.to_string(),
+            "Invalid products: #1 (sku SKU-2): missing price; #2: invalid price 'abc', missing quantity"
+        );
+    }
+
+    #[test]
+    fn test_parse_products_rejects_non_positive_quantity() {
+        let products = vec![product(&[
+            ("sku", "SKU-1"),
+            ("price", "10"),
+            ("quantity", "0"),
+        ])];
+
+        let error = parse_products(&products).unwrap_err();
+        assert_eq!(
+            error.products[0].issues,
+            vec![ProductIssue::InvalidQuantity("0".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_parse_products_empty() {
+        assert_eq!(parse_products(&[]).unwrap(), vec![]);
+    }
+}
@@ -0,0 +1,481 @@
+//! Replay of captured Edgee events.
+//!
+//! Captured events are read as JSON (one event per line in a JSONL file), converted to data-collection events,
+//! and run through the component with the given settings, to check a configuration against real traffic
+//! before deploying it.
+//!
+//! A captured event looks like:
+//! ```json
+//! {"uuid": "...", "timestamp": 1714566600, "type": "track", "consent": "granted",
+//!  "data": {"name": "urn:lla:llaPartnerConversion:123", "properties": {"value": 49.9, "currency": "USD"}},
+//!  "context": {"page": {"url": "https://example.com/checkout"}, "user": {"user_id": "123", "properties": {"email": "john@example.com"}},
+//!              "client": {"ip": "81.2.69.142"}, "campaign": {"source": "linkedin"}, "session": {"session_id": "..."}}}
+//! ```
+//! All fields but `type` and `timestamp` are optional. Properties are JSON objects, whose numbers and booleans
+//! are converted to strings.
+
+use std::collections::BTreeMap;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+use crate::exports::edgee::components::data_collection::{
+    Campaign, Client, Consent, Context, Data, Dict, EdgeeRequest, Event, EventType, Guest,
+    HttpMethod, PageData, Session, TrackData, UserData,
+};
+use crate::timestamp::parse_timestamp_millis;
+use crate::{track_request, LinkedinComponent};
+
+#[derive(Deserialize, Debug)]
+pub struct CapturedEvent {
+    #[serde(default)]
+    uuid: String,
+    #[serde(deserialize_with = "timestamp")]
+    timestamp: i64,
+    #[serde(default, deserialize_with = "consent")]
+    consent: Option<Consent>,
+    #[serde(flatten)]
+    data: CapturedData,
+    #[serde(default)]
+    context: CapturedContext,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", content = "data", rename_all = "lowercase")]
+enum CapturedData {
+    Page(#[serde(default)] CapturedPage),
+    Track(CapturedTrack),
+    User(#[serde(default)] CapturedUser),
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct CapturedTrack {
+    name: String,
+    #[serde(deserialize_with = "dict")]
+    properties: Dict,
+    #[serde(deserialize_with = "dicts")]
+    products: Vec<Dict>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct CapturedContext {
+    page: CapturedPage,
+    user: CapturedUser,
+    client: CapturedClient,
+    campaign: CapturedCampaign,
+    session: CapturedSession,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct CapturedPage {
+    name: String,
+    category: String,
+    keywords: Vec<String>,
+    title: String,
+    url: String,
+    path: String,
+    search: String,
+    referrer: String,
+    #[serde(deserialize_with = "dict")]
+    properties: Dict,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct CapturedUser {
+    user_id: String,
+    anonymous_id: String,
+    edgee_id: String,
+    #[serde(deserialize_with = "dict")]
+    properties: Dict,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct CapturedClient {
+    city: String,
+    ip: String,
+    locale: String,
+    timezone: String,
+    user_agent: String,
+    country_code: String,
+    country_name: String,
+    region: String,
+    continent: String,
+    os_name: String,
+    os_version: String,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct CapturedCampaign {
+    name: String,
+    source: String,
+    medium: String,
+    term: String,
+    content: String,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct CapturedSession {
+    session_id: String,
+    previous_session_id: String,
+    session_count: u32,
+    session_start: bool,
+    first_seen: i64,
+    last_seen: i64,
+}
+
+/// Unix timestamp in seconds or milliseconds, or ISO 8601 date, converted to milliseconds
+fn timestamp<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    let value = match Value::deserialize(deserializer)? {
+        Value::String(value) => value,
+        value => value.to_string(),
+    };
+    parse_timestamp_millis(&value)
+        .ok_or_else(|| serde::de::Error::custom(format!("invalid timestamp {value}")))
+}
+
+fn consent<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Consent>, D::Error> {
+    match Option::<String>::deserialize(deserializer)?.as_deref() {
+        None => Ok(None),
+        Some("granted") => Ok(Some(Consent::Granted)),
+        Some("denied") => Ok(Some(Consent::Denied)),
+        Some("pending") => Ok(Some(Consent::Pending)),
+        Some(consent) => Err(serde::de::Error::custom(format!(
+            "invalid consent {consent}, expected granted, denied or pending"
+        ))),
+    }
+}
+
+/// JSON object converted to a dictionary of strings, ignoring null values
+fn to_dict(object: BTreeMap<String, Value>) -> Dict {
+    object
+        .into_iter()
+        .filter_map(|(key, value)| match value {
+            Value::Null => None,
+            Value::String(value) => Some((key, value)),
+            value => Some((key, value.to_string())),
+        })
+        .collect()
+}
+
+fn dict<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Dict, D::Error> {
+    Ok(to_dict(BTreeMap::deserialize(deserializer)?))
+}
+
+fn dicts<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Dict>, D::Error> {
+    Ok(Vec::<BTreeMap<String, Value>>::deserialize(deserializer)?
+        .into_iter()
+        .map(to_dict)
+        .collect())
+}
+
+impl From<CapturedPage> for PageData {
+    fn from(page: CapturedPage) -> Self {
+        PageData {
+            name: page.name,
+            category: page.category,
+            keywords: page.keywords,
+            title: page.title,
+            url: page.url,
+            path: page.path,
+            search: page.search,
+            referrer: page.referrer,
+            properties: page.properties,
+        }
+    }
+}
+
+impl From<CapturedUser> for UserData {
+    fn from(user: CapturedUser) -> Self {
+        UserData {
+            user_id: user.user_id,
+            anonymous_id: user.anonymous_id,
+            edgee_id: user.edgee_id,
+            properties: user.properties,
+        }
+    }
+}
+
+impl CapturedEvent {
+    /// Data-collection event of the captured event
+    fn into_event(self) -> Event {
+        let (event_type, data) = match self.data {
+            CapturedData::Page(page) => (EventType::Page, Data::Page(page.into())),
+            CapturedData::Track(track) => (
+                EventType::Track,
+                Data::Track(TrackData {
+                    name: track.name,
+                    properties: track.properties,
+                    products: track.products,
+                }),
+            ),
+            CapturedData::User(user) => (EventType::User, Data::User(user.into())),
+        };
+        let context = self.context;
+        let client = context.client;
+        let campaign = context.campaign;
+        let session = context.session;
+
+        Event {
+            uuid: self.uuid,
+            event_type,
+            timestamp: self.timestamp.div_euclid(1000),
+            timestamp_millis: self.timestamp,
+            timestamp_micros: self.timestamp.saturating_mul(1000),
+            consent: self.consent,
+            context: Context {
+                page: context.page.into(),
+                user: context.user.into(),
+                client: Client {
+                    city: client.city,
+                    ip: client.ip,
+                    locale: client.locale,
+                    timezone: client.timezone,
+                    user_agent: client.user_agent,
+                    user_agent_architecture: String::new(),
+                    user_agent_bitness: String::new(),
+                    user_agent_full_version_list: String::new(),
+                    user_agent_version_list: String::new(),
+                    user_agent_mobile: String::new(),
+                    user_agent_model: String::new(),
+                    os_name: client.os_name,
+                    os_version: client.os_version,
+                    screen_width: 0,
+                    screen_height: 0,
+                    screen_density: 0.0,
+                    continent: client.continent,
+                    country_code: client.country_code,
+                    country_name: client.country_name,
+                    region: client.region,
+                },
+                campaign: Campaign {
+                    name: campaign.name,
+                    source: campaign.source,
+                    medium: campaign.medium,
+                    term: campaign.term,
+                    content: campaign.content,
+                    creative_format: String::new(),
+                    marketing_tactic: String::new(),
+                },
+                session: Session {
+                    session_id: session.session_id,
+                    previous_session_id: session.previous_session_id,
+                    session_count: session.session_count,
+                    session_start: session.session_start,
+                    first_seen: session.first_seen,
+                    last_seen: session.last_seen,
+                },
+            },
+            data,
+        }
+    }
+}
+
+/// Request that would be sent to LinkedIn
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ReplayRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Value,
+}
+
+impl From<EdgeeRequest> for ReplayRequest {
+    fn from(request: EdgeeRequest) -> Self {
+        let method = match request.method {
+            HttpMethod::Get => "GET",
+            HttpMethod::Put => "PUT",
+            HttpMethod::Post => "POST",
+            HttpMethod::Delete => "DELETE",
+        };
+        ReplayRequest {
+            method: method.to_string(),
+            url: request.url,
+            // the access token is not printed in the report
+            headers: request
+                .headers
+                .into_iter()
+                .map(|(key, value)| match key.as_str() {
+                    "Authorization" => (key, "Bearer ***".to_string()),
+                    _ => (key, value),
+                })
+                .collect(),
+            body: serde_json::from_str(&request.body).unwrap_or(Value::String(request.body)),
+        }
+    }
+}
+
+/// Outcome of a replayed event
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "outcome", rename_all = "lowercase")]
+pub enum ReplayOutcome {
+    /// The event is sent to LinkedIn with this request, ex: without its invalid conversion value
+    Sent {
+        request: ReplayRequest,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        warnings: Vec<String>,
+    },
+    /// The event is skipped by the settings (rules, exclusions, sampling...)
+    Skipped { code: String, message: String },
+    /// The event is rejected
+    Error { code: String, message: String },
+}
+
+impl ReplayOutcome {
+    fn from_error(message: String) -> Self {
+        // errors are prefixed by their code, ex: `skipped_by_rule: internal team`
+        let code = match message.split_once(": ") {
+            Some((code, _))
+                if !code.is_empty()
+                    && code.bytes().all(|c| c.is_ascii_lowercase() || c == b'_') =>
+            {
+                code.to_string()
+            }
+            _ => "error".to_string(),
+        };
+        if code.starts_with("skipped_") {
+            ReplayOutcome::Skipped { code, message }
+        } else {
+            ReplayOutcome::Error { code, message }
+        }
+    }
+
+    /// Code of the outcome, used to aggregate a report
+    pub fn code(&self) -> &str {
+        match self {
+            ReplayOutcome::Sent { .. } => "sent",
+            ReplayOutcome::Skipped { code, .. } | ReplayOutcome::Error { code, .. } => code,
+        }
+    }
+}
+
+/// Parse a captured event, from a line of a JSONL file
+pub fn parse_captured_event(line: &str) -> anyhow::Result<CapturedEvent> {
+    serde_json::from_str(line).map_err(|e| anyhow!("invalid captured event: {e}"))
+}
+
+/// Run a captured event through the component
+pub fn replay_event(event: CapturedEvent, settings: Dict) -> ReplayOutcome {
+    let event = event.into_event();
+    let result = match event.event_type {
+        EventType::Page => {
+            LinkedinComponent::page(event, settings).map(|request| (request, vec![]))
+        }
+        EventType::Track => track_request(event, settings),
+        EventType::User => {
+            LinkedinComponent::user(event, settings).map(|request| (request, vec![]))
+        }
+    };
+    match result {
+        Ok((request, warnings)) => ReplayOutcome::Sent {
+            request: request.into(),
+            warnings,
+        },
+        Err(message) => ReplayOutcome::from_error(message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EVENTS: &str = include_str!("../fixtures/replay/events.jsonl");
+
+    fn sample_settings() -> Dict {
+        vec![
+            (
+                "linkedin_access_token".to_string(),
+                "test_token".to_string(),
+            ),
+            (
+                "linkedin_rules".to_string(),
+                r#"[{"conditions": [{"field": "page.path", "operator": "starts_with", "value": "/internal"}], "action": "skip", "reason": "internal team"}]"#
+                    .to_string(),
+            ),
+        ]
+    }
+
+    fn replay_fixtures() -> Vec<ReplayOutcome> {
+        EVENTS
+            .lines()
+            .map(|line| replay_event(parse_captured_event(line).unwrap(), sample_settings()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_captured_event() {
+        let event = parse_captured_event(EVENTS.lines().next().unwrap())
+            .unwrap()
+            .into_event();
+        assert_eq!(event.event_type, EventType::Track);
+        assert_eq!(event.timestamp_millis, 1714566600000);
+        assert_eq!(event.consent, Some(Consent::Granted));
+        let Data::Track(data) = event.data else {
+            panic!("expected track data");
+        };
+        assert!(data
+            .properties
+            .contains(&("value".to_string(), "49.9".to_string())));
+
+        assert!(parse_captured_event(r#"{"type": "track"}"#).is_err());
+        assert!(
+            parse_captured_event(r#"{"type": "track", "timestamp": 1, "consent": "yes"}"#).is_err()
+        );
+    }
+
+    #[test]
+    fn test_replay_event_outcomes() {
+        let codes: Vec<String> = replay_fixtures()
+            .iter()
+            .map(|outcome| outcome.code().to_string())
+            .collect();
+        assert_eq!(codes, ["sent", "skipped_by_rule", "error", "error"]);
+
+        let ReplayOutcome::Sent { request, warnings } = &replay_fixtures()[0] else {
+            panic!("expected a sent event");
+        };
+        assert!(warnings.is_empty());
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.body["conversionValue"]["amount"], "49.90");
+        assert!(request
+            .headers
+            .contains(&("Authorization".to_string(), "Bearer ***".to_string())));
+    }
+
+    #[test]
+    fn test_replay_event_warnings() {
+        let line = EVENTS.lines().next().unwrap().replace("49.9", r#""abc""#);
+        let event = parse_captured_event(&line).unwrap();
+        let ReplayOutcome::Sent { request, warnings } = replay_event(event, sample_settings())
+        else {
+            panic!("expected a sent event");
+        };
+        assert_eq!(request.body.get("conversionValue"), None);
+        assert_eq!(
+            warnings,
+            ["invalid_conversion_value: conversion value abc is not a number, sent without value"]
+        );
+    }
+
+    #[test]
+    fn test_replay_outcome_from_error() {
+        assert_eq!(
+            ReplayOutcome::from_error(
+                "token_expired: access token expired on 2024-05-01".to_string()
+            )
+            .code(),
+            "token_expired"
+        );
+        assert_eq!(
+            ReplayOutcome::from_error("Consent is not granted".to_string()).code(),
+            "error"
+        );
+    }
+}
@@ -0,0 +1,317 @@
+use std::fmt;
+
+use serde::Deserialize;
+
+/// API Error
+///
+/// Rest.li error body returned by the LinkedIn Marketing API.
+/// To know more about the error format, check the online documentation: https://learn.microsoft.com/en-us/linkedin/shared/api-guide/concepts/error-handling
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiError {
+    pub status: Option<u16>,
+    pub service_error_code: Option<u64>,
+    pub code: Option<String>,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub error_details: ErrorDetails,
+}
+
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorDetails {
+    #[serde(default)]
+    pub input_errors: Vec<InputError>,
+}
+
+/// Validation error of a field of the request body
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct InputError {
+    #[serde(default)]
+    pub code: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub input: InputErrorLocation,
+}
+
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InputErrorLocation {
+    #[serde(default)]
+    pub input_path: InputPath,
+}
+
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InputPath {
+    #[serde(default)]
+    pub field_path: String,
+}
+
+/// Response of a `BATCH_CREATE` request, with one status per element
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+struct BatchResponse {
+    elements: Vec<BatchElement>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+struct BatchElement {
+    status: u16,
+    error: Option<ApiError>,
+}
+
+/// What to do with a failed request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// Temporary failure, the same request can be sent again later
+    Retryable,
+    /// The request was rejected, it should be fixed before being sent again
+    InvalidPayload,
+    /// The access token is expired or revoked, it must be refreshed
+    AuthExpired,
+    /// The access token lacks a permission, ex: the `rw_conversions` scope
+    Forbidden,
+    /// Too many requests, the request can be sent again after a delay
+    RateLimited,
+}
+
+impl ErrorClass {
+    /// Stable identifier of the error class
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorClass::Retryable => "linkedin_retryable",
+            ErrorClass::InvalidPayload => "linkedin_invalid_payload",
+            ErrorClass::AuthExpired => "linkedin_auth_expired",
+            ErrorClass::Forbidden => "linkedin_forbidden",
+            ErrorClass::RateLimited => "linkedin_rate_limited",
+        }
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, ErrorClass::Retryable | ErrorClass::RateLimited)
+    }
+
+    /// Classify an error from its HTTP status and error body
+    pub fn classify(status: u16, error: Option<&ApiError>) -> Self {
+        let code = error.and_then(|error| error.code.as_deref());
+        if matches!(
+            code,
+            Some("EXPIRED_ACCESS_TOKEN" | "REVOKED_ACCESS_TOKEN" | "INVALID_ACCESS_TOKEN")
+        ) {
+            return ErrorClass::AuthExpired;
+        }
+
+        match status {
+            401 => ErrorClass::AuthExpired,
+            403 => ErrorClass::Forbidden,
+            429 => ErrorClass::RateLimited,
+            408 | 500..=599 => ErrorClass::Retryable,
+            _ => ErrorClass::InvalidPayload,
+        }
+    }
+}
+
+/// Error of one element of a batch request
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElementError {
+    /// Index of the element in the request
+    pub index: usize,
+    pub status: u16,
+    pub class: ErrorClass,
+    pub error: Option<ApiError>,
+}
+
+/// Response Error
+///
+/// Failed request, or batch request with failed elements.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponseError {
+    pub status: u16,
+    pub class: ErrorClass,
+    /// Error body of the request, when it is a LinkedIn error
+    pub error: Option<Box<ApiError>>,
+    /// Failed elements of a batch request
+    pub element_errors: Vec<ElementError>,
+}
+
+impl fmt::Display for ResponseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.class.code())?;
+        if !self.element_errors.is_empty() {
+            write!(
+                f,
+                "{} elements of the batch failed",
+                self.element_errors.len()
+            )?;
+            for element in self.element_errors.iter() {
+                write!(f, "; #{} ({})", element.index, element.status)?;
+                if let Some(error) = &element.error {
+                    write!(f, ": {}", error.message)?;
+                }
+            }
+            return Ok(());
+        }
+
+        write!(f, "LinkedIn API error {}", self.status)?;
+        if let Some(error) = &self.error {
+            if let Some(service_error_code) = error.service_error_code {
+                write!(f, " (serviceErrorCode {service_error_code})")?;
+            }
+            write!(f, ": {}", error.message)?;
+            for input_error in error.error_details.input_errors.iter() {
+                write!(
+                    f,
+                    "; {}: {}",
+                    input_error.input.input_path.field_path, input_error.description
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ResponseError {}
+
+/// Parse a LinkedIn API response.
+///
+/// Failed requests are classified from their status and error body, which may not be JSON
+/// (ex: errors of a proxy). Successful batch requests are checked for failed elements.
+pub fn parse_response(status: u16, body: &str) -> Result<(), ResponseError> {
+    if !(200..300).contains(&status) {
+        let error = serde_json::from_str::<ApiError>(body).ok();
+        return Err(ResponseError {
+            status,
+            class: ErrorClass::classify(status, error.as_ref()),
+            error: error.map(Box::new),
+            element_errors: vec![],
+        });
+    }
+
+    let Ok(batch) = serde_json::from_str::<BatchResponse>(body) else {
+        return Ok(());
+    };
+    let element_errors: Vec<ElementError> = batch
+        .elements
+        .into_iter()
+        .enumerate()
+        .filter(|(_, element)| !(200..300).contains(&element.status))
+        .map(|(index, element)| ElementError {
+            index,
+            status: element.status,
+            class: ErrorClass::classify(element.status, element.error.as_ref()),
+            error: element.error,
+        })
+        .collect();
+
+    // the batch is retryable only if all its failed elements are
+    let class = match element_errors.iter().find(|e| !e.class.is_retryable()) {
+        Some(element) => element.class,
+        None => match element_errors.first() {
+            Some(element) => element.class,
+            None => return Ok(()),
+        },
+    };
+
+    Err(ResponseError {
+        status,
+        class,
+        error: None,
+        element_errors,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_invalid_payload() {
+        let body = include_str!("../fixtures/responses/invalid_payload.json");
+        let error = parse_response(400, body).unwrap_err();
+        assert_eq!(error.class, ErrorClass::InvalidPayload);
+        assert!(!error.class.is_retryable());
+
+        let api_error = error.error.as_ref().unwrap();
+        assert_eq!(api_error.status, Some(400));
+        assert_eq!(api_error.error_details.input_errors.len(), 2);
+        assert_eq!(
+            api_error.error_details.input_errors[1]
+                .input
+                .input_path
+                .field_path,
+            "conversionHappenedAt"
+        );
+        assert!(error
+            .to_string()
+            .starts_with("linkedin_invalid_payload: LinkedIn API error 400: Multiple errors"));
+        assert!(error
+            .to_string()
+            .ends_with("; conversionHappenedAt: Conversion happened more than 90 days ago"));
+    }
+
+    #[test]
+    fn test_parse_auth_expired() {
+        for body in [
+            include_str!("../fixtures/responses/expired_token.json"),
+            include_str!("../fixtures/responses/revoked_token.json"),
+        ] {
+            let error = parse_response(401, body).unwrap_err();
+            assert_eq!(error.class, ErrorClass::AuthExpired);
+            assert_eq!(error.error.unwrap().service_error_code, Some(65601));
+        }
+    }
+
+    #[test]
+    fn test_parse_forbidden() {
+        let body = include_str!("../fixtures/responses/forbidden.json");
+        let error = parse_response(403, body).unwrap_err();
+        assert_eq!(error.class, ErrorClass::Forbidden);
+        assert_eq!(
+            error.to_string(),
+            "linkedin_forbidden: LinkedIn API error 403 (serviceErrorCode 100): Not enough permissions to access: partnerApiConversionEvents.BATCH_CREATE.20250601"
+        );
+    }
+
+    #[test]
+    fn test_parse_rate_limited() {
+        let body = include_str!("../fixtures/responses/rate_limited.json");
+        let error = parse_response(429, body).unwrap_err();
+        assert_eq!(error.class, ErrorClass::RateLimited);
+        assert!(error.class.is_retryable());
+    }
+
+    #[test]
+    fn test_parse_server_error() {
+        let body = include_str!("../fixtures/responses/server_error.json");
+        let error = parse_response(503, body).unwrap_err();
+        assert_eq!(error.class, ErrorClass::Retryable);
+
+        // error pages of proxies are not JSON
+        let error = parse_response(502, "<html>Bad Gateway</html>").unwrap_err();
+        assert_eq!(error.class, ErrorClass::Retryable);
+        assert_eq!(error.error, None);
+        assert_eq!(
+            error.to_string(),
+            "linkedin_retryable: LinkedIn API error 502"
+        );
+    }
+
+    #[test]
+    fn test_parse_batch() {
+        let body = include_str!("../fixtures/responses/batch_success.json");
+        assert_eq!(parse_response(200, body), Ok(()));
+        assert_eq!(parse_response(201, ""), Ok(()));
+
+        let body = include_str!("../fixtures/responses/batch_partial_failure.json");
+        let error = parse_response(200, body).unwrap_err();
+        assert_eq!(error.class, ErrorClass::InvalidPayload);
+        assert_eq!(error.element_errors.len(), 1);
+        assert_eq!(error.element_errors[0].index, 1);
+        assert_eq!(
+            error.to_string(),
+            "linkedin_invalid_payload: 1 elements of the batch failed; #1 (400): User must contain at least one user id or user info"
+        );
+    }
+}
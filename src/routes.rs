@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use serde::Deserialize;
+
+use crate::conversion::ConversionInput;
+
+/// Routes
+///
+/// Routing table picking the LinkedIn access token and conversion mapping of an event,
+/// configured as JSON in the `linkedin_routes` setting. Routes are evaluated in order,
+/// and the first route whose criteria all match is used:
+/// - `host`: host of the page, or one of its subdomains
+/// - `property` and `value`: property of the track event
+/// - `campaign_source`: source of the campaign
+///
+/// Events matching no route use the `linkedin_access_token` setting and their own conversion.
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(transparent)]
+pub struct Routes(pub Vec<Route>);
+
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Route {
+    #[serde(default)]
+    pub host: Option<String>,
+    #[serde(default)]
+    pub property: Option<String>,
+    #[serde(default)]
+    pub value: Option<String>,
+    #[serde(default)]
+    pub campaign_source: Option<String>,
+    /// Access token of the ad account, the `linkedin_access_token` setting is used when missing
+    #[serde(default)]
+    pub access_token: Option<String>,
+    /// Conversion URN of each event name, ex: `{"purchase": "urn:lla:llaPartnerConversion:123"}`
+    #[serde(default)]
+    pub conversions: HashMap<String, String>,
+}
+
+impl Routes {
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        let routes: Routes = serde_json::from_str(value)?;
+        for (index, route) in routes.0.iter().enumerate() {
+            if route.host.is_none() && route.property.is_none() && route.campaign_source.is_none() {
+                return Err(anyhow!(
+                    "route #{index} must have a host, property or campaign_source"
+                ));
+            }
+            if route.property.is_some() != route.value.is_some() {
+                return Err(anyhow!(
+                    "route #{index} must have both a property and a value"
+                ));
+            }
+            if route
+                .access_token
+                .as_ref()
+                .is_some_and(|token| token.trim().is_empty())
+            {
+                return Err(anyhow!("route #{index} has an empty access_token"));
+            }
+        }
+        Ok(routes)
+    }
+
+    /// First route matching the conversion
+    pub fn select(&self, input: &ConversionInput) -> Option<&Route> {
+        self.0.iter().find(|route| route.matches(input))
+    }
+}
+
+impl Route {
+    fn matches(&self, input: &ConversionInput) -> bool {
+        let host_matches = self.host.as_ref().is_none_or(|host| {
+            let host = host.trim().to_lowercase();
+            let page_host = input.page_host.to_lowercase();
+            page_host == host
+                || page_host
+                    .strip_suffix(host.as_str())
+                    .is_some_and(|subdomain| subdomain.ends_with('.'))
+        });
+        let property_matches = match (&self.property, &self.value) {
+            (Some(name), Some(value)) => input
+                .properties
+                .iter()
+                .any(|(key, property)| key == name && property == value),
+            _ => true,
+        };
+        let campaign_source_matches = self
+            .campaign_source
+            .as_ref()
+            .is_none_or(|source| source.eq_ignore_ascii_case(&input.campaign_source));
+
+        host_matches && property_matches && campaign_source_matches
+    }
+
+    /// Conversion URN of the event, the event's own conversion is kept when it is not mapped
+    pub fn conversion(&self, conversion: &str) -> String {
+        self.conversions
+            .get(conversion)
+            .cloned()
+            .unwrap_or_else(|| conversion.to_string())
+    }
+}
+
+/// Host of a URL, without port, ex: `www.example.com` for `https://www.example.com:8080/path`
+pub(crate) fn host_from_url(url: &str) -> &str {
+    let url = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = url.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    match host.rsplit_once(':') {
+        Some((hostname, port)) if port.bytes().all(|c| c.is_ascii_digit()) => hostname,
+        _ => host,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_routes() -> Routes {
+        Routes::parse(
+            r#"[
+                {"host": "brand-a.com", "access_token": "token-a", "conversions": {"purchase": "urn:lla:llaPartnerConversion:1"}},
+                {"property": "brand", "value": "b", "access_token": "token-b"},
+                {"campaign_source": "newsletter", "conversions": {"purchase": "urn:lla:llaPartnerConversion:3"}}
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_select_by_host() {
+        let routes = sample_routes();
+        for host in ["brand-a.com", "www.Brand-A.com"] {
+            let input = ConversionInput {
+                page_host: host.to_string(),
+                ..ConversionInput::default()
+            };
+            let route = routes.select(&input).unwrap();
+            assert_eq!(route.access_token.as_deref(), Some("token-a"));
+            assert_eq!(
+                route.conversion("purchase"),
+                "urn:lla:llaPartnerConversion:1"
+            );
+            assert_eq!(route.conversion("lead"), "lead");
+        }
+
+        let input = ConversionInput {
+            page_host: "notbrand-a.com".to_string(),
+            ..ConversionInput::default()
+        };
+        assert_eq!(routes.select(&input), None);
+    }
+
+    #[test]
+    fn test_select_by_property() {
+        let input = ConversionInput {
+            properties: vec![("brand".to_string(), "b".to_string())],
+            ..ConversionInput::default()
+        };
+        let routes = sample_routes();
+        let route = routes.select(&input).unwrap();
+        assert_eq!(route.access_token.as_deref(), Some("token-b"));
+    }
+
+    #[test]
+    fn test_select_by_campaign_source() {
+        let input = ConversionInput {
+            campaign_source: "Newsletter".to_string(),
+            ..ConversionInput::default()
+        };
+        let routes = sample_routes();
+        let route = routes.select(&input).unwrap();
+        assert_eq!(route.access_token, None);
+        assert_eq!(
+            route.conversion("purchase"),
+            "urn:lla:llaPartnerConversion:3"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Routes::parse("not json").is_err());
+        assert!(Routes::parse(r#"[{"access_token": "token"}]"#).is_err());
+        assert!(Routes::parse(r#"[{"property": "brand"}]"#).is_err());
+        assert!(Routes::parse(r#"[{"host": "a.com", "access_token": " "}]"#).is_err());
+        assert!(Routes::parse(r#"[{"hostname": "a.com"}]"#).is_err());
+    }
+
+    #[test]
+    fn test_host_from_url() {
+        assert_eq!(
+            host_from_url("https://www.example.com/path?q=1"),
+            "www.example.com"
+        );
+        assert_eq!(
+            host_from_url("https://user@example.com:8080"),
+            "example.com"
+        );
+        assert_eq!(host_from_url("example.com/path"), "example.com");
+        assert_eq!(
+            host_from_url("https://[2001:db8::1]:8080/"),
+            "[2001:db8::1]"
+        );
+        assert_eq!(host_from_url(""), "");
+    }
+}
@@ -0,0 +1,456 @@
+use serde::Deserialize;
+
+use crate::attribution::is_linkedin_attributed;
+use crate::conversion::ConversionInput;
+use crate::conversion_rules::check_urn;
+use crate::skip::SkipReason;
+
+/// Rules
+///
+/// Filtering rules evaluated before an event is sent to LinkedIn CAPI, configured as JSON
+/// in the `linkedin_rules` setting. Rules are evaluated in order, and the first rule whose
+/// conditions all match decides what happens to the event:
+/// - `send`: the event is sent as is
+/// - `skip`: the event is not sent, with an optional `reason`
+/// - `rewrite`: the event is sent to another `conversion` rule
+///
+/// Events matching no rule are sent.
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(transparent)]
+pub struct Rules(pub Vec<Rule>);
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Rule {
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    #[serde(flatten)]
+    pub action: Action,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Action {
+    Send,
+    Skip {
+        #[serde(default)]
+        reason: Option<String>,
+    },
+    Rewrite {
+        conversion: String,
+    },
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Condition {
+    pub field: Field,
+    pub operator: Operator,
+    #[serde(default)]
+    pub value: Option<ConditionValue>,
+}
+
+/// Event field a condition applies to
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(try_from = "String")]
+pub enum Field {
+    /// `event`: name of the track event
+    Event,
+    /// `property.<name>`: property of the track event
+    Property(String),
+    /// `page.path`
+    PagePath,
+    /// `client.country_code`
+    CountryCode,
+    /// `client.user_agent`
+    UserAgent,
+    /// `session.session_count`
+    SessionCount,
+    /// `campaign.source`
+    CampaignSource,
+    /// `campaign.medium`
+    CampaignMedium,
+    /// `campaign.name`
+    CampaignName,
+    /// `attribution.linkedin`: `true` when the session was acquired from LinkedIn
+    LinkedinAttributed,
+}
+
+impl TryFrom<String> for Field {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "event" => Ok(Field::Event),
+            "page.path" => Ok(Field::PagePath),
+            "client.country_code" => Ok(Field::CountryCode),
+            "client.user_agent" => Ok(Field::UserAgent),
+            "session.session_count" => Ok(Field::SessionCount),
+            "campaign.source" => Ok(Field::CampaignSource),
+            "campaign.medium" => Ok(Field::CampaignMedium),
+            "campaign.name" => Ok(Field::CampaignName),
+            "attribution.linkedin" => Ok(Field::LinkedinAttributed),
+            _ => match value.strip_prefix("property.") {
+                Some(name) if !name.is_empty() => Ok(Field::Property(name.to_string())),
+                _ => Err(format!("unknown field '{value}'")),
+            },
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Operator {
+    /// The field is set and not empty
+    Exists,
+    Equals,
+    NotEquals,
+    /// Case-insensitive substring match, against any of the values when a list is given
+    Contains,
+    StartsWith,
+    In,
+    NotIn,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum ConditionValue {
+    Number(f64),
+    Text(String),
+    List(Vec<String>),
+}
+
+impl ConditionValue {
+    fn texts(&self) -> Vec<String> {
+        match self {
+            ConditionValue::Number(number) => vec![number.to_string()],
+            ConditionValue::Text(text) => vec![text.clone()],
+            ConditionValue::List(list) => list.clone(),
+        }
+    }
+
+    fn number(&self) -> Option<f64> {
+        match self {
+            ConditionValue::Number(number) => Some(*number),
+            ConditionValue::Text(text) => text.trim().parse().ok(),
+            ConditionValue::List(_) => None,
+        }
+    }
+}
+
+/// Event fields the rules are evaluated against
+#[derive(Debug, Default, Clone)]
+pub struct RuleInput<'a> {
+    pub event_name: &'a str,
+    pub properties: &'a [(String, String)],
+    pub page_path: &'a str,
+    pub country_code: &'a str,
+    pub user_agent: &'a str,
+    pub session_count: u32,
+    pub campaign_source: &'a str,
+    pub campaign_medium: &'a str,
+    pub campaign_name: &'a str,
+    pub linkedin_attributed: bool,
+}
+
+impl<'a> RuleInput<'a> {
+    pub fn new(input: &'a ConversionInput) -> Self {
+        Self {
+            event_name: &input.conversion,
+            properties: &input.properties,
+            page_path: &input.page_path,
+            country_code: &input.country_code,
+            user_agent: &input.user_agent,
+            session_count: input.session_count,
+            campaign_source: &input.campaign_source,
+            campaign_medium: &input.campaign_medium,
+            campaign_name: &input.campaign_name,
+            linkedin_attributed: is_linkedin_attributed(input),
+        }
+    }
+}
+
+/// Outcome of the rules for an event that is not skipped
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    Send,
+    Rewrite(String),
+}
+
+impl Rules {
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        let rules: Rules = serde_json::from_str(value)?;
+        for (index, rule) in rules.0.iter().enumerate() {
+            for condition in rule.conditions.iter() {
+                if condition.operator != Operator::Exists && condition.value.is_none() {
+                    return Err(anyhow::anyhow!(
+                        "rule #{index}: operator {:?} requires a value",
+                        condition.operator
+                    ));
+                }
+            }
+            if let Action::Rewrite { conversion } = &rule.action {
+                check_urn(conversion, "urn:lla:llaPartnerConversion:")
+                    .map_err(|e| anyhow::anyhow!("rule #{index}: {e}"))?;
+            }
+        }
+        Ok(rules)
+    }
+
+    pub fn evaluate(&self, input: &RuleInput) -> Result<Decision, SkipReason> {
+        let rule = self.0.iter().enumerate().find(|(_, rule)| {
+            rule.conditions
+                .iter()
+                .all(|condition| condition.matches(input))
+        });
+
+        let Some((index, rule)) = rule else {
+            return Ok(Decision::Send);
+        };
+
+        match &rule.action {
+            Action::Send => Ok(Decision::Send),
+            Action::Rewrite { conversion } => Ok(Decision::Rewrite(conversion.clone())),
+            Action::Skip { reason } => Err(SkipReason::Rule(
+                reason.clone().unwrap_or_else(|| format!("rule #{index}")),
+            )),
+        }
+    }
+}
+
+impl Condition {
+    fn matches(&self, input: &RuleInput) -> bool {
+        let field = match &self.field {
+            Field::Event => Some(input.event_name.to_string()),
+            Field::Property(name) => input
+                .properties
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.clone()),
+            Field::PagePath => Some(input.page_path.to_string()),
+            Field::CountryCode => Some(input.country_code.to_string()),
+            Field::UserAgent => Some(input.user_agent.to_string()),
+            Field::SessionCount => Some(input.session_count.to_string()),
+            Field::CampaignSource => Some(input.campaign_source.to_string()),
+            Field::CampaignMedium => Some(input.campaign_medium.to_string()),
+            Field::CampaignName => Some(input.campaign_name.to_string()),
+            Field::LinkedinAttributed => Some(input.linkedin_attributed.to_string()),
+        };
+        let field = field.filter(|field| !field.is_empty());
+
+        let (field, value) = match (self.operator, field, &self.value) {
+            (Operator::Exists, field, _) => return field.is_some(),
+            // A missing field only matches negative operators
+            (Operator::NotEquals | Operator::NotIn, None, _) => return true,
+            (_, None, _) | (_, _, None) => return false,
+            (_, Some(field), Some(value)) => (field, value),
+        };
+
+        let compare =
+            |ordering: fn(f64, f64) -> bool| match (field.trim().parse::<f64>(), value.number()) {
+                (Ok(field), Some(value)) => ordering(field, value),
+                _ => false,
+            };
+
+        match self.operator {
+            Operator::Exists => true,
+            Operator::Equals => value.texts().contains(&field),
+            Operator::NotEquals => value.texts().iter().all(|value| *value != field),
+            Operator::Contains => {
+                let field = field.to_lowercase();
+                value
+                    .texts()
+                    .iter()
+                    .any(|value| field.contains(&value.to_lowercase()))
+            }
+            Operator::StartsWith => value.texts().iter().any(|value| field.starts_with(value)),
+            Operator::In => value.texts().contains(&field),
+            Operator::NotIn => !value.texts().contains(&field),
+            Operator::Gt => compare(|field, value| field > value),
+            Operator::Gte => compare(|field, value| field >= value),
+            Operator::Lt => compare(|field, value| field < value),
+            Operator::Lte => compare(|field, value| field <= value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_properties() -> Vec<(String, String)> {
+        vec![
+            ("env".to_string(), "staging".to_string()),
+            ("value".to_string(), "42".to_string()),
+        ]
+    }
+
+    fn sample_input(properties: &[(String, String)]) -> RuleInput<'_> {
+        RuleInput {
+            event_name: "urn:lla:llaPartnerConversion:123",
+            properties,
+            page_path: "/checkout/success",
+            country_code: "FR",
+            user_agent: "Mozilla/5.0 (compatible; Googlebot/2.1)",
+            session_count: 3,
+            campaign_source: "linkedin",
+            campaign_medium: "paid_social",
+            campaign_name: "spring_sale",
+            linkedin_attributed: true,
+        }
+    }
+
+    #[test]
+    fn test_no_rules_sends() {
+        let properties = sample_properties();
+        let rules = Rules::parse("[]").unwrap();
+        assert_eq!(
+            rules.evaluate(&sample_input(&properties)),
+            Ok(Decision::Send)
+        );
+    }
+
+    #[test]
+    fn test_skip_on_property() {
+        let properties = sample_properties();
+        let rules = Rules::parse(
+            r#"[{"conditions": [{"field": "property.env", "operator": "equals", "value": "staging"}], "action": "skip", "reason": "test traffic"}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            rules.evaluate(&sample_input(&properties)),
+            Err(SkipReason::Rule("test traffic".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_skip_default_reason() {
+        let properties = sample_properties();
+        let rules = Rules::parse(
+            r#"[
+                {"conditions": [{"field": "page.path", "operator": "starts_with", "value": "/internal"}], "action": "skip"},
+                {"conditions": [{"field": "client.user_agent", "operator": "contains", "value": ["bot", "crawler"]}], "action": "skip"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            rules.evaluate(&sample_input(&properties)),
+            Err(SkipReason::Rule("rule #1".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_rewrite_on_country_and_session() {
+        let properties = sample_properties();
+        let rules = Rules::parse(
+            r#"[{"conditions": [
+                {"field": "client.country_code", "operator": "in", "value": ["FR", "DE"]},
+                {"field": "session.session_count", "operator": "gt", "value": 1},
+                {"field": "event", "operator": "equals", "value": "urn:lla:llaPartnerConversion:123"}
+            ], "action": "rewrite", "conversion": "urn:lla:llaPartnerConversion:456"}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            rules.evaluate(&sample_input(&properties)),
+            Ok(Decision::Rewrite(
+                "urn:lla:llaPartnerConversion:456".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_rewrite_on_campaign() {
+        let properties = sample_properties();
+        let rules = Rules::parse(
+            r#"[{"conditions": [
+                {"field": "attribution.linkedin", "operator": "equals", "value": "true"},
+                {"field": "campaign.medium", "operator": "equals", "value": "paid_social"},
+                {"field": "campaign.name", "operator": "starts_with", "value": "spring"}
+            ], "action": "rewrite", "conversion": "urn:lla:llaPartnerConversion:789"}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            rules.evaluate(&sample_input(&properties)),
+            Ok(Decision::Rewrite(
+                "urn:lla:llaPartnerConversion:789".to_string()
+            ))
+        );
+
+        let input = RuleInput {
+            linkedin_attributed: false,
+            ..sample_input(&properties)
+        };
+        assert_eq!(rules.evaluate(&input), Ok(Decision::Send));
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let properties = sample_properties();
+        let rules = Rules::parse(
+            r#"[
+                {"conditions": [{"field": "property.value", "operator": "gte", "value": "42"}], "action": "send"},
+                {"conditions": [], "action": "skip"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            rules.evaluate(&sample_input(&properties)),
+            Ok(Decision::Send)
+        );
+    }
+
+    #[test]
+    fn test_conditions_must_all_match() {
+        let properties = sample_properties();
+        let rules = Rules::parse(
+            r#"[{"conditions": [
+                {"field": "client.country_code", "operator": "equals", "value": "FR"},
+                {"field": "session.session_count", "operator": "lt", "value": 2}
+            ], "action": "skip"}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            rules.evaluate(&sample_input(&properties)),
+            Ok(Decision::Send)
+        );
+    }
+
+    #[test]
+    fn test_missing_property() {
+        let properties = sample_properties();
+        let rules = Rules::parse(
+            r#"[
+                {"conditions": [{"field": "property.internal", "operator": "exists"}], "action": "skip", "reason": "internal"},
+                {"conditions": [{"field": "property.internal", "operator": "not_equals", "value": "true"}], "action": "skip", "reason": "not internal"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            rules.evaluate(&sample_input(&properties)),
+            Err(SkipReason::Rule("not internal".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Rules::parse("not json").is_err());
+        assert!(Rules::parse(
+            r#"[{"conditions": [{"field": "unknown", "operator": "exists"}], "action": "skip"}]"#
+        )
+        .is_err());
+        assert!(Rules::parse(
+            r#"[{"conditions": [{"field": "event", "operator": "equals"}], "action": "skip"}]"#
+        )
+        .is_err());
+        assert!(Rules::parse(r#"[{"conditions": [], "action": "rewrite"}]"#).is_err());
+        assert!(Rules::parse(r#"[{"conditions": [], "action": "drop"}]"#).is_err());
+        assert_eq!(
+            Rules::parse(r#"[{"conditions": [], "action": "rewrite", "conversion": "purchase"}]"#)
+                .unwrap_err()
+                .to_string(),
+            "rule #0: Invalid URN purchase, expected urn:lla:llaPartnerConversion:<id>"
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+
+use crate::linkedin_payload::hash_value;
+use crate::skip::SkipReason;
+
+/// Number of buckets users are spread across, giving a sample rate precision of 0.0001%
+const BUCKETS: u64 = 1_000_000;
+
+/// Sampling
+///
+/// Per-conversion sample rates, used to only send a share of high-volume conversions.
+/// Users are assigned to a bucket by hashing their identifier with the conversion,
+/// so a given user is consistently in or out of the sample for a conversion.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Sampling {
+    pub rates: HashMap<String, f64>,
+}
+
+impl Sampling {
+    pub fn new(rates: &HashMap<String, String>) -> anyhow::Result<Self> {
+        let rates = rates
+            .iter()
+            .map(|(conversion, rate)| {
+                let rate = rate
+                    .trim()
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|rate| (0.0..=1.0).contains(rate))
+                    .ok_or_else(|| {
+                        anyhow!("Invalid sample rate for {conversion}: {rate}, expected a number between 0 and 1")
+                    })?;
+                Ok((conversion.clone(), rate))
+            })
+            .collect::<anyhow::Result<HashMap<String, f64>>>()?;
+
+        Ok(Self { rates })
+    }
+
+    /// Check whether the conversion of this user is part of the sample.
+    /// Conversions without a sample rate are always sent.
+    pub fn check(&self, conversion: &str, user_key: &str) -> Result<(), SkipReason> {
+        match self.rates.get(conversion) {
+            Some(rate) if !is_sampled(conversion, user_key, *rate) => {
+                Err(SkipReason::Sampled(conversion.to_string()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Deterministically decide whether a user is part of the sample of a conversion
+pub fn is_sampled(conversion: &str, user_key: &str, rate: f64) -> bool {
+    bucket(conversion, user_key) < (rate * BUCKETS as f64).round() as u64
+}
+
+fn bucket(conversion: &str, user_key: &str) -> u64 {
+    let hash = hash_value(&format!("{conversion}:{user_key}"));
+    // the hash is 64 hex chars, the first 16 are a u64
+    hash.get(..16)
+        .and_then(|prefix| u64::from_str_radix(prefix, 16).ok())
+        .unwrap_or_default()
+        % BUCKETS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONVERSION: &str = "urn:lla:llaPartnerConversion:123";
+
+    fn sampled_share(conversion: &str, rate: f64, users: usize) -> f64 {
+        let sampled = (0..users)
+            .filter(|i| is_sampled(conversion, &format!("user-{i}"), rate))
+            .count();
+        sampled as f64 / users as f64
+    }
+
+    #[test]
+    fn test_is_sampled_is_deterministic() {
+        for i in 0..100 {
+            let user = format!("user-{i}");
+            assert_eq!(
+                is_sampled(CONVERSION, &user, 0.5),
+                is_sampled(CONVERSION, &user, 0.5)
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_sampled_bounds() {
+        for i in 0..1000 {
+            let user = format!("user-{i}");
+            assert!(!is_sampled(CONVERSION, &user, 0.0));
+            assert!(is_sampled(CONVERSION, &user, 1.0));
+        }
+    }
+
+    #[test]
+    fn test_is_sampled_is_monotonic() {
+        // a user in the sample at a given rate stays in the sample at a higher rate
+        for i in 0..1000 {
+            let user = format!("user-{i}");
+            if is_sampled(CONVERSION, &user, 0.1) {
+                assert!(is_sampled(CONVERSION, &user, 0.2));
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_sampled_distribution() {
+        for rate in [0.01, 0.1, 0.25, 0.5, 0.9] {
+            let share = sampled_share(CONVERSION, rate, 20_000);
+            // more than 5 standard deviations of a binomial distribution
+            let tolerance = 5.0 * (rate * (1.0 - rate) / 20_000.0).sqrt();
+            assert!(
+                (share - rate).abs() < tolerance,
+                "rate {rate}: sampled share {share}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_sampled_independent_between_conversions() {
+        let other = "urn:lla:llaPartnerConversion:456";
+        let both = (0..20_000)
+            .map(|i| format!("user-{i}"))
+            .filter(|user| is_sampled(CONVERSION, user, 0.5) && is_sampled(other, user, 0.5))
+            .count();
+        let share = both as f64 / 20_000.0;
+        assert!((share - 0.25).abs() < 0.02, "share in both samples {share}");
+    }
+
+    #[test]
+    fn test_sampling_check() {
+        let rates = HashMap::from([(CONVERSION.to_string(), "0".to_string())]);
+        let sampling = Sampling::new(&rates).unwrap();
+        assert_eq!(
+            sampling.check(CONVERSION, "user"),
+            Err(SkipReason::Sampled(CONVERSION.to_string()))
+        );
+        assert_eq!(
+            sampling.check("urn:lla:llaPartnerConversion:456", "user"),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_sampling_new_invalid() {
+        for rate in ["abc", "-0.1", "1.5", "NaN"] {
+            let rates = HashMap::from([(CONVERSION.to_string(), rate.to_string())]);
+            assert!(Sampling::new(&rates).is_err(), "{rate} should be invalid");
+        }
+    }
+}
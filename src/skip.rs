@@ -0,0 +1,69 @@
+use std::fmt;
+
+/// Skip Reason
+///
+/// Reason why an event is deliberately not sent to LinkedIn CAPI.
+/// Skipped events are reported to Edgee as errors, whose message starts with the skip code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    /// A filtering rule matched the event
+    Rule(String),
+    /// The user agent is a known bot
+    Bot(String),
+    /// The client IP is in the IP denylist
+    DeniedIp(String),
+    /// The email domain is in the email domain denylist
+    DeniedEmailDomain(String),
+    /// The user is not part of the sample of the conversion
+    Sampled(String),
+    /// The session was not acquired from LinkedIn, in the `linkedin_only` attribution mode
+    NotLinkedinAttributed(String),
+}
+
+impl SkipReason {
+    /// Stable identifier of the skip reason
+    pub fn code(&self) -> &'static str {
+        match self {
+            SkipReason::Rule(_) => "skipped_by_rule",
+            SkipReason::Bot(_) => "skipped_bot",
+            SkipReason::DeniedIp(_) => "skipped_denied_ip",
+            SkipReason::DeniedEmailDomain(_) => "skipped_denied_email_domain",
+            SkipReason::Sampled(_) => "skipped_sampled",
+            SkipReason::NotLinkedinAttributed(_) => "skipped_not_linkedin_attributed",
+        }
+    }
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.code())?;
+        match self {
+            SkipReason::Rule(reason) => write!(f, "{reason}"),
+            SkipReason::Bot(user_agent) => write!(f, "bot user agent: {user_agent}"),
+            SkipReason::DeniedIp(ip) => write!(f, "client IP {ip} is denied"),
+            SkipReason::DeniedEmailDomain(domain) => {
+                write!(f, "email domain {domain} is denied")
+            }
+            SkipReason::Sampled(conversion) => {
+                write!(f, "user is not sampled for conversion {conversion}")
+            }
+            SkipReason::NotLinkedinAttributed(source) => {
+                write!(f, "session was acquired from {source}, not LinkedIn")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SkipReason {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skip_reason_display() {
+        let skip = SkipReason::Rule("test traffic".to_string());
+        assert_eq!(skip.code(), "skipped_by_rule");
+        assert_eq!(skip.to_string(), "skipped_by_rule: test traffic");
+    }
+}
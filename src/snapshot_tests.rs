@@ -0,0 +1,156 @@
+//! Snapshot tests of the requests sent to LinkedIn CAPI.
+//!
+//! Each case runs a track event through the component, and compares the emitted `EdgeeRequest`
+//! (or error) with the expected JSON checked in under `snapshots/`.
+//! Run `make test.bless` (or `BLESS=1 cargo test --lib snapshot`) to update them after an intended change.
+
+use std::fs;
+use std::path::PathBuf;
+
+use pretty_assertions::assert_eq;
+use serde_json::{json, Value};
+
+use crate::exports::edgee::components::data_collection::{
+    Consent, Dict, EdgeeRequest, Event, Guest, HttpMethod,
+};
+use crate::payload_schema::assert_valid_payload;
+use crate::test_support::{sample_products, settings, EventBuilder, CONVERSION};
+use crate::LinkedinComponent;
+
+const LI_FAT_ID: &str = "df2a1a2b-1c5e-4c4b-8f7d-1f6f4c3b2a10";
+
+/// Granted track event of a known user, whose fields are all deterministic
+fn sample_event() -> EventBuilder {
+    EventBuilder::track(CONVERSION)
+        .with_timestamp_millis(1714566600000)
+        .with_properties(&[])
+        .with_user_id("user-123")
+        .with_anonymous_id("")
+        .with_edgee_id("edgee-456")
+        .without_user_properties()
+        .with_email("john@example.com")
+        .with_page_url("https://example.com/checkout")
+        .with_page_search("")
+        .with_ip("81.2.69.142")
+        .with_user_agent("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) Chrome/120.0.0.0")
+        .with_locale("en-US")
+}
+
+fn sample_settings() -> Dict {
+    settings(&[("linkedin_access_token", "test_token")])
+}
+
+fn request_json(request: &EdgeeRequest) -> Value {
+    // the Debug output of the bindings is not stable across wit-bindgen versions
+    let method = match request.method {
+        HttpMethod::Get => "GET",
+        HttpMethod::Put => "PUT",
+        HttpMethod::Post => "POST",
+        HttpMethod::Delete => "DELETE",
+    };
+    json!({
+        "method": method,
+        "url": request.url,
+        "headers": request.headers,
+        "forward_client_headers": request.forward_client_headers,
+        "body": serde_json::from_str::<Value>(&request.body).unwrap_or(Value::String(request.body.clone())),
+    })
+}
+
+/// Compare the result of a track event with its snapshot, or write it when blessing
+fn assert_snapshot(name: &str, event: Event, settings: Dict) {
+    let actual = match LinkedinComponent::track(event, settings) {
+        Ok(request) => {
+            assert_valid_payload(&request.body);
+            request_json(&request)
+        }
+        Err(error) => json!({ "error": error }),
+    };
+    let actual = serde_json::to_string_pretty(&actual).unwrap() + "\n";
+
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("snapshots")
+        .join(format!("{name}.json"));
+
+    if std::env::var("BLESS").is_ok_and(|bless| bless == "1") {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "missing snapshot {}, run `make test.bless` to create it",
+            path.display()
+        )
+    });
+    assert_eq!(expected, actual, "snapshot {name} changed");
+}
+
+#[test]
+fn snapshot_email() {
+    assert_snapshot("email", sample_event().build(), sample_settings());
+}
+
+#[test]
+fn snapshot_without_email() {
+    let event = sample_event().without_email().build();
+    assert_snapshot("without_email", event, sample_settings());
+}
+
+#[test]
+fn snapshot_li_fat_id_from_url() {
+    let event = sample_event()
+        .with_page_search(&format!("?utm_source=linkedin&li_fat_id={LI_FAT_ID}"))
+        .build();
+    assert_snapshot("li_fat_id_from_url", event, sample_settings());
+}
+
+#[test]
+fn snapshot_li_fat_id_from_user_property() {
+    let event = sample_event()
+        .with_user_property("_li_fat_id", LI_FAT_ID)
+        .build();
+    assert_snapshot("li_fat_id_from_user_property", event, sample_settings());
+}
+
+#[test]
+fn snapshot_value() {
+    let event = sample_event()
+        .with_properties(&[
+            ("value", "49.9"),
+            ("currency", "usd"),
+            ("event_id", "order-1"),
+        ])
+        .build();
+    assert_snapshot("value", event, sample_settings());
+}
+
+#[test]
+fn snapshot_product_conversions() {
+    let event = sample_event()
+        .with_properties(&[("currency", "EUR")])
+        .with_products(sample_products())
+        .build();
+    let settings = settings(&[
+        ("linkedin_access_token", "test_token"),
+        ("linkedin_value_from_products", "true"),
+        (
+            "linkedin_product_conversions",
+            "SKU-1=urn:lla:llaPartnerConversion:1",
+        ),
+    ]);
+    assert_snapshot("product_conversions", event, settings);
+}
+
+#[test]
+fn snapshot_consent_pending() {
+    let event = sample_event().with_consent(Consent::Pending).build();
+    assert_snapshot("consent_pending", event, sample_settings());
+}
+
+#[test]
+fn snapshot_consent_denied() {
+    let event = sample_event().with_consent(Consent::Denied).build();
+    assert_snapshot("consent_denied", event, sample_settings());
+}
@@ -0,0 +1,305 @@
+//! Fixtures shared by the tests.
+//!
+//! `EventBuilder` builds data-collection events from a complete default fixture, so a test only states
+//! what matters to it:
+//! ```ignore
+//! let event = EventBuilder::track(CONVERSION).with_consent(Consent::Denied).without_email().build();
+//! ```
+
+use crate::exports::edgee::components::data_collection::{
+    Campaign, Client, Consent, Context, Data, Dict, Event, EventType, PageData, Session, TrackData,
+    UserData,
+};
+
+/// Conversion rule of the default track event
+pub const CONVERSION: &str = "urn:lla:llaPartnerConversion:123";
+
+/// Settings with only an access token
+pub fn sample_settings() -> Dict {
+    settings(&[("linkedin_access_token", "abc")])
+}
+
+pub fn settings(settings: &[(&str, &str)]) -> Dict {
+    settings
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+pub fn sample_products() -> Vec<Dict> {
+    vec![
+        vec![
+            ("sku".to_string(), "SKU-1".to_string()),
+            ("price".to_string(), "10.5".to_string()),
+            ("quantity".to_string(), "2".to_string()),
+        ],
+        vec![
+            ("sku".to_string(), "SKU-2".to_string()),
+            ("price".to_string(), "4".to_string()),
+            ("quantity".to_string(), "1".to_string()),
+        ],
+    ]
+}
+
+/// Fluent builder of data-collection events
+#[derive(Debug, Clone)]
+pub struct EventBuilder {
+    event: Event,
+}
+
+impl EventBuilder {
+    /// Track event of a conversion, with a `USD` currency and a few properties
+    pub fn track(name: &str) -> Self {
+        Self::new(
+            EventType::Track,
+            Data::Track(TrackData {
+                name: name.to_string(),
+                products: vec![],
+                properties: vec![
+                    ("prop1".to_string(), "value1".to_string()),
+                    ("prop2".to_string(), "10".to_string()),
+                    ("currency".to_string(), "USD".to_string()),
+                ],
+            }),
+        )
+    }
+
+    pub fn page() -> Self {
+        let page = sample_context().page;
+        Self::new(EventType::Page, Data::Page(page))
+    }
+
+    /// User event, whose data is the user of the context
+    pub fn user() -> Self {
+        let user = sample_context().user;
+        Self::new(EventType::User, Data::User(user))
+    }
+
+    fn new(event_type: EventType, data: Data) -> Self {
+        Self {
+            event: Event {
+                uuid: "3f1c2b7e-8a4d-4e21-9c6b-5d0f7a9e1b23".to_string(),
+                timestamp: 123,
+                timestamp_millis: 123,
+                timestamp_micros: 123,
+                event_type,
+                data,
+                context: sample_context(),
+                consent: Some(Consent::Granted),
+            },
+        }
+    }
+
+    pub fn build(self) -> Event {
+        self.event
+    }
+
+    pub fn with_uuid(mut self, uuid: &str) -> Self {
+        self.event.uuid = uuid.to_string();
+        self
+    }
+
+    pub fn with_timestamp_millis(mut self, timestamp_millis: i64) -> Self {
+        self.event.timestamp = timestamp_millis / 1000;
+        self.event.timestamp_millis = timestamp_millis;
+        self.event.timestamp_micros = timestamp_millis * 1000;
+        self
+    }
+
+    pub fn with_consent(mut self, consent: Consent) -> Self {
+        self.event.consent = Some(consent);
+        self
+    }
+
+    pub fn without_consent(mut self) -> Self {
+        self.event.consent = None;
+        self
+    }
+
+    pub fn with_user_id(self, user_id: &str) -> Self {
+        self.with_user(|user| user.user_id = user_id.to_string())
+    }
+
+    pub fn with_anonymous_id(self, anonymous_id: &str) -> Self {
+        self.with_user(|user| user.anonymous_id = anonymous_id.to_string())
+    }
+
+    pub fn with_edgee_id(self, edgee_id: &str) -> Self {
+        self.with_user(|user| user.edgee_id = edgee_id.to_string())
+    }
+
+    /// Set a user property, replacing its previous value
+    pub fn with_user_property(self, key: &str, value: &str) -> Self {
+        self.with_user(|user| {
+            user.properties.retain(|(k, _)| k != key);
+            user.properties.push((key.to_string(), value.to_string()));
+        })
+    }
+
+    pub fn without_user_property(self, key: &str) -> Self {
+        self.with_user(|user| user.properties.retain(|(k, _)| k != key))
+    }
+
+    pub fn without_user_properties(self) -> Self {
+        self.with_user(|user| user.properties.clear())
+    }
+
+    pub fn with_email(self, email: &str) -> Self {
+        self.with_user_property("email", email)
+    }
+
+    pub fn without_email(self) -> Self {
+        self.without_user_property("email")
+    }
+
+    /// LinkedIn click id in the query string of the page
+    pub fn with_li_fat_id(self, li_fat_id: &str) -> Self {
+        self.with_page_search(&format!("?li_fat_id={li_fat_id}"))
+    }
+
+    pub fn with_page_search(mut self, search: &str) -> Self {
+        self.event.context.page.search = search.to_string();
+        self
+    }
+
+    pub fn with_page_url(mut self, url: &str) -> Self {
+        self.event.context.page.url = url.to_string();
+        self
+    }
+
+    pub fn with_ip(mut self, ip: &str) -> Self {
+        self.event.context.client.ip = ip.to_string();
+        self
+    }
+
+    pub fn with_user_agent(mut self, user_agent: &str) -> Self {
+        self.event.context.client.user_agent = user_agent.to_string();
+        self
+    }
+
+    pub fn with_locale(mut self, locale: &str) -> Self {
+        self.event.context.client.locale = locale.to_string();
+        self
+    }
+
+    pub fn with_session_start(mut self, session_start: bool) -> Self {
+        self.event.context.session.session_start = session_start;
+        self
+    }
+
+    /// Set a property of the track event, replacing its previous value
+    pub fn with_property(self, key: &str, value: &str) -> Self {
+        self.with_track(|data| {
+            data.properties.retain(|(k, _)| k != key);
+            data.properties.push((key.to_string(), value.to_string()));
+        })
+    }
+
+    /// Replace all the properties of the track event
+    pub fn with_properties(self, properties: &[(&str, &str)]) -> Self {
+        self.with_track(|data| data.properties = settings(properties))
+    }
+
+    pub fn with_value(self, value: &str) -> Self {
+        self.with_property("value", value)
+    }
+
+    pub fn with_products(self, products: Vec<Dict>) -> Self {
+        self.with_track(|data| data.products = products)
+    }
+
+    fn with_user(mut self, update: impl FnOnce(&mut UserData)) -> Self {
+        update(&mut self.event.context.user);
+        if let Data::User(ref mut user) = self.event.data {
+            *user = self.event.context.user.clone();
+        }
+        self
+    }
+
+    fn with_track(mut self, update: impl FnOnce(&mut TrackData)) -> Self {
+        match self.event.data {
+            Data::Track(ref mut data) => update(data),
+            _ => panic!("not a track event"),
+        }
+        self
+    }
+}
+
+fn sample_context() -> Context {
+    Context {
+        page: PageData {
+            name: "page name".to_string(),
+            category: "category".to_string(),
+            keywords: vec!["value1".to_string(), "value2".into()],
+            title: "page title".to_string(),
+            url: "https://example.com/full-url?test=1".to_string(),
+            path: "/full-path".to_string(),
+            search: "?test=1".to_string(),
+            referrer: "https://example.com/another-page".to_string(),
+            properties: vec![
+                ("prop1".to_string(), "value1".to_string()),
+                ("prop2".to_string(), "10".to_string()),
+                ("prop3".to_string(), "true".to_string()),
+                ("prop4".to_string(), "false".to_string()),
+                ("currency".to_string(), "USD".to_string()),
+            ],
+        },
+        user: UserData {
+            user_id: "123".to_string(),
+            anonymous_id: "456".to_string(),
+            edgee_id: "abc".to_string(),
+            properties: vec![
+                ("email".to_string(), "test@test.com".to_string()),
+                ("phone_number".to_string(), "+39 1231231231".to_string()),
+                ("first_name".to_string(), "John".to_string()),
+                ("last_name".to_string(), "Doe".to_string()),
+                ("gender".to_string(), "Male".to_string()),
+                ("date_of_birth".to_string(), "1979-12-31".to_string()),
+                ("city".to_string(), "Las Vegas".to_string()),
+                ("state".to_string(), "Nevada".to_string()),
+                ("zip_code".to_string(), "11111".to_string()),
+                ("country".to_string(), "USA".to_string()),
+                ("random_property".to_string(), "abc".to_string()), // will be ignored
+            ],
+        },
+        client: Client {
+            city: "Paris".to_string(),
+            ip: "192.168.0.1".to_string(),
+            locale: "fr".to_string(),
+            timezone: "CET".to_string(),
+            user_agent: "Chrome".to_string(),
+            user_agent_architecture: "x86".to_string(),
+            user_agent_bitness: "64".to_string(),
+            user_agent_full_version_list: "abc".to_string(),
+            user_agent_version_list: "abc".to_string(),
+            user_agent_mobile: "mobile".to_string(),
+            user_agent_model: "don't know".to_string(),
+            os_name: "MacOS".to_string(),
+            os_version: "latest".to_string(),
+            screen_width: 1024,
+            screen_height: 768,
+            screen_density: 2.0,
+            continent: "Europe".to_string(),
+            country_code: "FR".to_string(),
+            country_name: "France".to_string(),
+            region: "West Europe".to_string(),
+        },
+        campaign: Campaign {
+            name: "random".to_string(),
+            source: "random".to_string(),
+            medium: "random".to_string(),
+            term: "random".to_string(),
+            content: "random".to_string(),
+            creative_format: "random".to_string(),
+            marketing_tactic: "random".to_string(),
+        },
+        session: Session {
+            session_id: "random".to_string(),
+            previous_session_id: "random".to_string(),
+            session_count: 2,
+            session_start: true,
+            first_seen: 123,
+            last_seen: 123,
+        },
+    }
+}
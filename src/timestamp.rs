@@ -0,0 +1,239 @@
+/// Epoch values below this are seconds, above are milliseconds (year 5138 in seconds, 1973 in milliseconds)
+const SECONDS_THRESHOLD: i64 = 100_000_000_000;
+
+/// Parse a timestamp into milliseconds since the Unix epoch.
+///
+/// Accepts Unix timestamps in seconds or milliseconds, and ISO 8601 dates:
+/// `2024-05-01`, `2024-05-01T12:30:00Z`, `2024-05-01 12:30:00.123+02:00`.
+/// Dates without a timezone are UTC.
+pub fn parse_timestamp_millis(value: &str) -> Option<i64> {
+    let value = value.trim();
+
+    if let Ok(epoch) = value.parse::<i64>() {
+        return if epoch.abs() < SECONDS_THRESHOLD {
+            epoch.checked_mul(1000)
+        } else {
+            Some(epoch)
+        };
+    }
+
+    let (date, time) = match value.split_once(['T', 't', ' ']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (value, None),
+    };
+
+    let mut parts = date.splitn(3, '-');
+    let year = parse_number(parts.next()?, 4)?;
+    let month = parse_number(parts.next()?, 2)?;
+    let day = parse_number(parts.next()?, 2)?;
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+
+    let mut millis = days_from_civil(year, month, day) * 86_400_000;
+
+    if let Some(time) = time {
+        let (time, offset_millis) = split_offset(time)?;
+        let (time, fraction) = match time.split_once('.') {
+            Some((time, fraction)) => (time, Some(fraction)),
+            None => (time, None),
+        };
+
+        let mut parts = time.splitn(3, ':');
+        let hours = parse_number(parts.next()?, 2)?;
+        let minutes = parse_number(parts.next()?, 2)?;
+        let seconds = match parts.next() {
+            Some(seconds) => parse_number(seconds, 2)?,
+            None => 0,
+        };
+        if hours > 23 || minutes > 59 || seconds > 60 {
+            return None;
+        }
+
+        let fraction_millis = match fraction {
+            Some(fraction)
+                if !fraction.is_empty() && fraction.bytes().all(|c| c.is_ascii_digit()) =>
+            {
+                // keep milliseconds precision
+                format!("{fraction:0<3}")[..3].parse::<i64>().ok()?
+            }
+            Some(_) => return None,
+            None => 0,
+        };
+
+        millis += ((hours * 60 + minutes) * 60 + seconds) * 1000 + fraction_millis;
+        millis -= offset_millis;
+    }
+
+    Some(millis)
+}
+
+/// Format a timestamp in milliseconds as an ISO 8601 date, ex: `2024-05-01`
+pub fn format_date(millis: i64) -> String {
+    let (year, month, day) = civil_from_days(millis.div_euclid(86_400_000));
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Split the timezone of a time, returning the offset in milliseconds
+fn split_offset(time: &str) -> Option<(&str, i64)> {
+    if let Some(time) = time.strip_suffix(['Z', 'z']) {
+        return Some((time, 0));
+    }
+    match time.rfind(['+', '-']) {
+        Some(index) => {
+            let sign = if time[index..].starts_with('-') {
+                -1
+            } else {
+                1
+            };
+            let offset = &time[index + 1..];
+            let (hours, minutes) = match offset.split_once(':') {
+                Some((hours, minutes)) => (hours, minutes),
+                None if offset.len() == 4 => offset.split_at(2),
+                None => (offset, "00"),
+            };
+            let hours = parse_number(hours, 2)?;
+            let minutes = parse_number(minutes, 2)?;
+            Some((&time[..index], sign * (hours * 60 + minutes) * 60_000))
+        }
+        None => Some((time, 0)),
+    }
+}
+
+fn parse_number(value: &str, digits: usize) -> Option<i64> {
+    if value.len() != digits || !value.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    value.parse().ok()
+}
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Number of days since 1970-01-01 of a date of the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Date of the proleptic Gregorian calendar from a number of days since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_epoch() {
+        assert_eq!(parse_timestamp_millis("1714566600"), Some(1714566600000));
+        assert_eq!(parse_timestamp_millis("1714566600123"), Some(1714566600123));
+        assert_eq!(parse_timestamp_millis(" 0 "), Some(0));
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_timestamp_millis("1970-01-01"), Some(0));
+        assert_eq!(parse_timestamp_millis("2024-05-01"), Some(1714521600000));
+        assert_eq!(parse_timestamp_millis("2024-02-29"), Some(1709164800000));
+    }
+
+    #[test]
+    fn test_parse_date_time() {
+        assert_eq!(
+            parse_timestamp_millis("2024-05-01T12:30:00Z"),
+            Some(1714566600000)
+        );
+        assert_eq!(
+            parse_timestamp_millis("2024-05-01 12:30:00"),
+            Some(1714566600000)
+        );
+        assert_eq!(
+            parse_timestamp_millis("2024-05-01T12:30Z"),
+            Some(1714566600000)
+        );
+        assert_eq!(
+            parse_timestamp_millis("2024-05-01T12:30:00.5Z"),
+            Some(1714566600500)
+        );
+        assert_eq!(
+            parse_timestamp_millis("2024-05-01T12:30:00.123456Z"),
+            Some(1714566600123)
+        );
+        assert_eq!(
+            parse_timestamp_millis("2024-05-01T14:30:00+02:00"),
+            Some(1714566600000)
+        );
+        assert_eq!(
+            parse_timestamp_millis("2024-05-01T07:30:00-0500"),
+            Some(1714566600000)
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        for value in [
+            "",
+            "abc",
+            "2024-13-01",
+            "2023-02-29",
+            "2024-05-32",
+            "24-05-01",
+            "2024-05-01T25:00:00Z",
+            "2024-05-01T12:30:00.abcZ",
+            "2024-05-01T12:30:00+2",
+        ] {
+            assert_eq!(
+                parse_timestamp_millis(value),
+                None,
+                "{value} should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_date() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(1714566600000), "2024-05-01");
+        assert_eq!(format_date(1709164800000), "2024-02-29");
+        assert_eq!(format_date(-1), "1969-12-31");
+    }
+
+    #[test]
+    fn test_civil_roundtrip() {
+        for days in -800_000..800_000 {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+}
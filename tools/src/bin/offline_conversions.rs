@@ -0,0 +1,601 @@
+//! Offline conversions importer
+//!
+//! Reads offline conversions (e.g. closed-won deals exported from a CRM) from a CSV or JSONL file,
+//! and writes the LinkedIn CAPI `BATCH_CREATE` bodies, ready to be POSTed to
+//! `https://api.linkedin.com/rest/conversionEvents` with the `X-RestLi-Method: BATCH_CREATE` header.
+//!
+//! Each row has the following columns (or JSON fields):
+//! - `email` (required): email of the user, hashed before being sent
+//! - `conversion` (required): URN of the conversion rule, ex: `urn:lla:llaPartnerConversion:123`
+//! - `timestamp` (required): Unix timestamp in seconds or milliseconds, or ISO 8601 date
+//! - `value`, `currency` (optional): conversion value
+//! - `event_id` (optional): deduplication id, derived from the row when missing
+//!
+//! Usage: `offline_conversions <input.csv|input.jsonl> [--format csv|jsonl] [--out <dir>] [--batch-size <n>]`
+//!
+//! Without `--out`, one batch body is printed per line on stdout. The validation report is printed on stderr.
+
+#![cfg_attr(not(test), deny(clippy::unwrap_used, clippy::expect_used))]
+
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use anyhow::{anyhow, Context as _};
+use serde::{Deserialize, Deserializer};
+
+use linkedin_capi_component::conversion::{Consent, ConversionInput};
+use linkedin_capi_component::exclusions::Exclusions;
+use linkedin_capi_component::external_id::ExternalId;
+use linkedin_capi_component::linkedin_payload::{hash_value, LinkedinEvent, LinkedinPayload};
+use linkedin_capi_component::timestamp::parse_timestamp_millis;
+
+/// Maximum number of conversions in a LinkedIn CAPI batch request
+const MAX_BATCH_SIZE: usize = 5000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Csv,
+    Jsonl,
+}
+
+#[derive(Debug)]
+struct Args {
+    input: PathBuf,
+    format: Format,
+    out: Option<PathBuf>,
+    batch_size: usize,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct Row {
+    #[serde(default, deserialize_with = "string_or_number")]
+    email: Option<String>,
+    #[serde(default, deserialize_with = "string_or_number")]
+    conversion: Option<String>,
+    #[serde(default, deserialize_with = "string_or_number")]
+    timestamp: Option<String>,
+    #[serde(default, deserialize_with = "string_or_number")]
+    value: Option<String>,
+    #[serde(default, deserialize_with = "string_or_number")]
+    currency: Option<String>,
+    #[serde(default, deserialize_with = "string_or_number")]
+    event_id: Option<String>,
+}
+
+/// JSONL rows may have numeric timestamps and values
+fn string_or_number<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Value {
+        Integer(i64),
+        Float(f64),
+        Text(String),
+    }
+
+    Ok(
+        Option::<Value>::deserialize(deserializer)?.and_then(|value| match value {
+            Value::Integer(value) => Some(value.to_string()),
+            Value::Float(value) => Some(value.to_string()),
+            Value::Text(value) if value.trim().is_empty() => None,
+            Value::Text(value) => Some(value),
+        }),
+    )
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("error: {e}");
+            eprintln!("usage: offline_conversions <input.csv|input.jsonl> [--format csv|jsonl] [--out <dir>] [--batch-size <n>]");
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(&args) {
+        Ok(0) => ExitCode::SUCCESS,
+        Ok(_) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("error: {e:#}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> anyhow::Result<Args> {
+    let mut input = None;
+    let mut format = None;
+    let mut out = None;
+    let mut batch_size = MAX_BATCH_SIZE;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                format = match args.next().as_deref() {
+                    Some("csv") => Some(Format::Csv),
+                    Some("jsonl") => Some(Format::Jsonl),
+                    _ => return Err(anyhow!("--format must be csv or jsonl")),
+                }
+            }
+            "--out" => {
+                out = Some(PathBuf::from(
+                    args.next()
+                        .ok_or_else(|| anyhow!("--out requires a directory"))?,
+                ))
+            }
+            "--batch-size" => {
+                batch_size = args
+                    .next()
+                    .and_then(|size| size.parse().ok())
+                    .filter(|size| (1..=MAX_BATCH_SIZE).contains(size))
+                    .ok_or_else(|| anyhow!("--batch-size must be between 1 and {MAX_BATCH_SIZE}"))?
+            }
+            _ if input.is_none() && !arg.starts_with("--") => input = Some(PathBuf::from(arg)),
+            _ => return Err(anyhow!("unexpected argument {arg}")),
+        }
+    }
+
+    let input = input.ok_or_else(|| anyhow!("missing input file"))?;
+    let format = match format {
+        Some(format) => format,
+        None => match input.extension().and_then(|ext| ext.to_str()) {
+            Some("csv") => Format::Csv,
+            Some("jsonl") | Some("ndjson") => Format::Jsonl,
+            _ => {
+                return Err(anyhow!(
+                    "cannot guess the format of the input, use --format"
+                ))
+            }
+        },
+    };
+
+    Ok(Args {
+        input,
+        format,
+        out,
+        batch_size,
+    })
+}
+
+/// Build the batches and print the validation report, returning the number of invalid rows
+fn run(args: &Args) -> anyhow::Result<usize> {
+    let rows = read_rows(&args.input, args.format)?;
+
+    let mut events = vec![];
+    let mut invalid = 0;
+    for (line, row) in rows {
+        match row.and_then(build_event) {
+            Ok((event, warnings)) if warnings.is_empty() => {
+                eprintln!("line {line}: ok");
+                events.push(event);
+            }
+            Ok((event, warnings)) => {
+                for warning in warnings.iter() {
+                    eprintln!("line {line}: warning: {warning}");
+                }
+                events.push(event);
+            }
+            Err(e) => {
+                eprintln!("line {line}: error: {e}");
+                invalid += 1;
+            }
+        }
+    }
+
+    let batches = build_batches(&events, args.batch_size)?;
+
+    match &args.out {
+        Some(dir) => write_batches(dir, &batches)?,
+        None => {
+            let mut stdout = io::stdout().lock();
+            for batch in batches.iter() {
+                writeln!(stdout, "{batch}")?;
+            }
+        }
+    }
+
+    eprintln!(
+        "{} valid rows, {} invalid rows, {} batches",
+        events.len(),
+        invalid,
+        batches.len()
+    );
+
+    Ok(invalid)
+}
+
+/// Read the rows of the input file, with their line number
+fn read_rows(path: &Path, format: Format) -> anyhow::Result<Vec<(usize, anyhow::Result<Row>)>> {
+    let file = File::open(path).with_context(|| format!("cannot open {}", path.display()))?;
+
+    match format {
+        Format::Csv => {
+            let mut reader = csv::ReaderBuilder::new()
+                .trim(csv::Trim::All)
+                .from_reader(file);
+            Ok(reader
+                .deserialize::<Row>()
+                .enumerate()
+                .map(|(i, row)| (i + 2, row.map_err(anyhow::Error::from)))
+                .collect())
+        }
+        Format::Jsonl => Ok(BufReader::new(file)
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !matches!(line, Ok(line) if line.trim().is_empty()))
+            .map(|(i, line)| {
+                let row = line
+                    .map_err(anyhow::Error::from)
+                    .and_then(|line| serde_json::from_str::<Row>(&line).map_err(Into::into));
+                (i + 1, row)
+            })
+            .collect()),
+    }
+}
+
+/// `BATCH_CREATE` bodies of the events, with at most `batch_size` events each.
+/// Raw PII in fields that are not hashed is redacted, as by the component with its default settings.
+fn build_batches(events: &[LinkedinEvent], batch_size: usize) -> anyhow::Result<Vec<String>> {
+    let mut batches = vec![];
+    for (i, chunk) in events.chunks(batch_size).enumerate() {
+        let payload = LinkedinPayload {
+            events: chunk.to_vec(),
+            ..LinkedinPayload::default()
+        };
+        let (body, redacted) = payload.pii_guard.apply(payload.batch_body()?)?;
+        for finding in redacted.iter() {
+            eprintln!("batch {}: warning: pii_redacted: {finding} redacted", i + 1);
+        }
+        batches.push(body);
+    }
+    Ok(batches)
+}
+
+fn write_batches(dir: &Path, batches: &[String]) -> anyhow::Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("cannot create {}", dir.display()))?;
+    for (i, batch) in batches.iter().enumerate() {
+        let path = dir.join(format!("batch-{:04}.json", i + 1));
+        fs::write(&path, batch).with_context(|| format!("cannot write {}", path.display()))?;
+        eprintln!("wrote {}", path.display());
+    }
+    Ok(())
+}
+
+/// Validate a row and build its LinkedIn event, along with the warnings about the row,
+/// ex: a value sent without its currency
+fn build_event(row: Row) -> anyhow::Result<(LinkedinEvent, Vec<String>)> {
+    let email = row
+        .email
+        .filter(|email| email.contains('@'))
+        .ok_or_else(|| anyhow!("missing or invalid email"))?;
+    let conversion = row
+        .conversion
+        .filter(|conversion| conversion.starts_with("urn:"))
+        .ok_or_else(|| anyhow!("missing or invalid conversion URN"))?;
+    let timestamp = row.timestamp.ok_or_else(|| anyhow!("missing timestamp"))?;
+    let timestamp_millis = parse_timestamp_millis(&timestamp)
+        .ok_or_else(|| anyhow!("invalid timestamp {timestamp}"))?;
+
+    // deterministic, so that importing the same file twice is deduplicated by LinkedIn
+    let event_id = row
+        .event_id
+        .unwrap_or_else(|| hash_value(&format!("{email}:{conversion}:{timestamp_millis}")));
+
+    let mut properties = vec![];
+    if let Some(value) = row.value {
+        properties.push(("value".to_string(), value));
+    }
+    if let Some(currency) = row.currency {
+        properties.push(("currency".to_string(), currency));
+    }
+
+    // offline conversions are imported from a CRM, where the consent has already been collected
+    let input = ConversionInput {
+        conversion,
+        timestamp_millis,
+        event_id,
+        email: Some(email),
+        consent: Some(Consent::Granted),
+        properties,
+        ..ConversionInput::default()
+    };
+
+    let event = LinkedinEvent::new(
+        &input,
+        &input.conversion,
+        &Exclusions::default(),
+        &ExternalId::default(),
+    )?;
+    let mut payload = LinkedinPayload::default();
+    payload.add_track_event(event, &input.properties, &input.products)?;
+    let event = payload
+        .events
+        .pop()
+        .ok_or_else(|| anyhow!("no event built"))?;
+    Ok((event, payload.warnings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::Value;
+
+    fn args(args: &[&str]) -> anyhow::Result<Args> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../fixtures/offline_conversions")
+            .join(name)
+    }
+
+    fn sample_row() -> Row {
+        Row {
+            email: Some("john@example.com".to_string()),
+            conversion: Some("urn:lla:llaPartnerConversion:123".to_string()),
+            timestamp: Some("1714566600".to_string()),
+            ..Row::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let parsed = args(&["deals.csv"]).unwrap();
+        assert_eq!(parsed.input, PathBuf::from("deals.csv"));
+        assert_eq!(parsed.format, Format::Csv);
+        assert_eq!(parsed.out, None);
+        assert_eq!(parsed.batch_size, MAX_BATCH_SIZE);
+
+        let parsed = args(&["deals.ndjson", "--out", "batches", "--batch-size", "10"]).unwrap();
+        assert_eq!(parsed.format, Format::Jsonl);
+        assert_eq!(parsed.out, Some(PathBuf::from("batches")));
+        assert_eq!(parsed.batch_size, 10);
+
+        let parsed = args(&["--format", "jsonl", "export.txt"]).unwrap();
+        assert_eq!(parsed.format, Format::Jsonl);
+    }
+
+    #[test]
+    fn test_parse_args_errors() {
+        for (arguments, error) in [
+            (vec![], "missing input file"),
+            (
+                vec!["export.txt"],
+                "cannot guess the format of the input, use --format",
+            ),
+            (
+                vec!["deals.csv", "--format", "xml"],
+                "--format must be csv or jsonl",
+            ),
+            (vec!["deals.csv", "--out"], "--out requires a directory"),
+            (
+                vec!["deals.csv", "--batch-size", "0"],
+                "--batch-size must be between 1 and 5000",
+            ),
+            (
+                vec!["deals.csv", "--batch-size", "5001"],
+                "--batch-size must be between 1 and 5000",
+            ),
+            (
+                vec!["deals.csv", "other.csv"],
+                "unexpected argument other.csv",
+            ),
+            (
+                vec!["deals.csv", "--dry-run"],
+                "unexpected argument --dry-run",
+            ),
+        ] {
+            assert_eq!(
+                args(&arguments).unwrap_err().to_string(),
+                error,
+                "{arguments:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_read_rows_csv() {
+        let rows = read_rows(&fixture("deals.csv"), Format::Csv).unwrap();
+        let lines: Vec<usize> = rows.iter().map(|(line, _)| *line).collect();
+        assert_eq!(lines, vec![2, 3, 4, 5]);
+
+        let rows: Vec<Row> = rows.into_iter().map(|(_, row)| row.unwrap()).collect();
+        assert_eq!(rows[0].email.as_deref(), Some("john@example.com"));
+        // numeric cells are read as numbers
+        assert_eq!(rows[0].value.as_deref(), Some("1250.5"));
+        assert_eq!(rows[0].event_id.as_deref(), Some("deal-1"));
+        // empty cells are missing values
+        assert_eq!(rows[1].email, None);
+        assert_eq!(rows[1].value, None);
+        // cells are trimmed
+        assert_eq!(rows[2].email.as_deref(), Some("jane@example.com"));
+        assert_eq!(
+            rows[2].conversion.as_deref(),
+            Some("urn:lla:llaPartnerConversion:456")
+        );
+    }
+
+    #[test]
+    fn test_read_rows_jsonl() {
+        let rows = read_rows(&fixture("deals.jsonl"), Format::Jsonl).unwrap();
+        // the blank line is skipped, but still counted
+        let lines: Vec<usize> = rows.iter().map(|(line, _)| *line).collect();
+        assert_eq!(lines, vec![1, 3, 4]);
+
+        let row = rows[0].1.as_ref().unwrap();
+        assert_eq!(row.timestamp.as_deref(), Some("1714566600"));
+        assert_eq!(row.value.as_deref(), Some("1250.5"));
+        assert!(rows[1].1.is_err());
+        assert_eq!(rows[2].1.as_ref().unwrap().value, None);
+    }
+
+    #[test]
+    fn test_read_rows_missing_file() {
+        let error = read_rows(&fixture("missing.csv"), Format::Csv).unwrap_err();
+        assert!(error.to_string().starts_with("cannot open "));
+    }
+
+    #[test]
+    fn test_build_event() {
+        let (event, warnings) = build_event(Row {
+            value: Some("1250.50".to_string()),
+            currency: Some("EUR".to_string()),
+            event_id: Some("deal-1".to_string()),
+            ..sample_row()
+        })
+        .unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(event.conversion, "urn:lla:llaPartnerConversion:123");
+        assert_eq!(event.event_time, 1714566600000);
+        assert_eq!(event.event_id, "deal-1");
+        assert_eq!(
+            event.user_data.user_ids[0].id_value,
+            hash_value("john@example.com")
+        );
+        let value = event.conversion_value.unwrap();
+        assert_eq!(value.currency_code, "EUR");
+        assert_eq!(value.amount, "1250.50");
+    }
+
+    #[test]
+    fn test_build_event_derived_event_id() {
+        let (event, _) = build_event(sample_row()).unwrap();
+        assert_eq!(
+            event.event_id,
+            hash_value("john@example.com:urn:lla:llaPartnerConversion:123:1714566600000")
+        );
+        assert_eq!(
+            build_event(sample_row()).unwrap().0.event_id,
+            event.event_id
+        );
+        assert!(event.conversion_value.is_none());
+    }
+
+    #[test]
+    fn test_build_event_warnings() {
+        // rows of the fixture with an invalid value are imported without their value, and reported
+        let rows = read_rows(&fixture("deals.csv"), Format::Csv).unwrap();
+        let (line, row) = rows.into_iter().last().unwrap();
+        assert_eq!(line, 5);
+        let (event, warnings) = build_event(row.unwrap()).unwrap();
+        assert_eq!(event.conversion_value, None);
+        assert_eq!(
+            warnings,
+            ["invalid_conversion_value: conversion value abc is not a number, sent without value"]
+        );
+
+        let (_, warnings) = build_event(Row {
+            value: Some("10".to_string()),
+            ..sample_row()
+        })
+        .unwrap();
+        assert_eq!(
+            warnings,
+            ["missing_conversion_currency: conversion value 10 has no currency, sent without value"]
+        );
+    }
+
+    #[test]
+    fn test_build_event_errors() {
+        for (row, error) in [
+            (
+                Row {
+                    email: None,
+                    ..sample_row()
+                },
+                "missing or invalid email",
+            ),
+            (
+                Row {
+                    email: Some("john".to_string()),
+                    ..sample_row()
+                },
+                "missing or invalid email",
+            ),
+            (
+                Row {
+                    conversion: Some("123".to_string()),
+                    ..sample_row()
+                },
+                "missing or invalid conversion URN",
+            ),
+            (
+                Row {
+                    timestamp: None,
+                    ..sample_row()
+                },
+                "missing timestamp",
+            ),
+            (
+                Row {
+                    timestamp: Some("yesterday".to_string()),
+                    ..sample_row()
+                },
+                "invalid timestamp yesterday",
+            ),
+        ] {
+            assert_eq!(build_event(row).unwrap_err().to_string(), error);
+        }
+    }
+
+    #[test]
+    fn test_build_batches() {
+        let events: Vec<LinkedinEvent> = (0..5)
+            .map(|i| {
+                build_event(Row {
+                    event_id: Some(format!("deal-{i}")),
+                    ..sample_row()
+                })
+                .unwrap()
+                .0
+            })
+            .collect();
+
+        let batches = build_batches(&events, 2).unwrap();
+        let batches: Vec<Value> = batches
+            .iter()
+            .map(|batch| serde_json::from_str(batch).unwrap())
+            .collect();
+        let event_ids: Vec<Vec<&str>> = batches
+            .iter()
+            .map(|batch| {
+                batch["elements"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|element| element["eventId"].as_str().unwrap())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            event_ids,
+            vec![
+                vec!["deal-0", "deal-1"],
+                vec!["deal-2", "deal-3"],
+                vec!["deal-4"]
+            ]
+        );
+
+        assert_eq!(build_batches(&events, MAX_BATCH_SIZE).unwrap().len(), 1);
+
+        // raw emails in the event ids are redacted
+        let (event, _) = build_event(Row {
+            event_id: Some("john@example.com".to_string()),
+            ..sample_row()
+        })
+        .unwrap();
+        let batches = build_batches(&[event], 2).unwrap();
+        let batch: Value = serde_json::from_str(&batches[0]).unwrap();
+        assert_eq!(
+            batch["elements"][0]["eventId"],
+            hash_value("john@example.com")
+        );
+        assert!(build_batches(&[], 2).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,137 @@
+//! Replay of captured Edgee events
+//!
+//! Runs captured events (one JSON event per line) through the component with the given settings,
+//! to check a configuration against real traffic before deploying it.
+//!
+//! Usage: `replay <events.jsonl> [--settings <settings.json>] [--set <key>=<value>]...`
+//!
+//! `--settings` is a JSON object of the component settings, ex: `{"linkedin_access_token": "..."}`,
+//! and `--set` overrides one setting.
+//!
+//! The outcome of each line is printed on stdout as JSON: the request sent to LinkedIn,
+//! or the code and message of the skip reason or error. The aggregated counts are printed on stderr.
+
+#![cfg_attr(not(test), deny(clippy::unwrap_used, clippy::expect_used))]
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use anyhow::{anyhow, Context as _};
+use serde_json::{json, Value};
+
+use linkedin_capi_component::replay::{parse_captured_event, replay_event, ReplayOutcome};
+
+#[derive(Debug)]
+struct Args {
+    input: PathBuf,
+    settings: Vec<(String, String)>,
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("error: {e:#}");
+            eprintln!("usage: replay <events.jsonl> [--settings <settings.json>] [--set <key>=<value>]...");
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e:#}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> anyhow::Result<Args> {
+    let mut input = None;
+    let mut settings = BTreeMap::new();
+    let mut overrides = vec![];
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--settings" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| anyhow!("--settings requires a file"))?;
+                settings = read_settings(&path)?;
+            }
+            "--set" => {
+                let setting = args
+                    .next()
+                    .ok_or_else(|| anyhow!("--set requires a key=value setting"))?;
+                let (key, value) = setting
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("--set requires a key=value setting"))?;
+                overrides.push((key.trim().to_string(), value.to_string()));
+            }
+            _ if input.is_none() && !arg.starts_with("--") => input = Some(PathBuf::from(arg)),
+            _ => return Err(anyhow!("unexpected argument {arg}")),
+        }
+    }
+
+    settings.extend(overrides);
+    Ok(Args {
+        input: input.ok_or_else(|| anyhow!("missing input file"))?,
+        settings: settings.into_iter().collect(),
+    })
+}
+
+/// Settings file, a JSON object whose numbers and booleans are converted to strings
+fn read_settings(path: &str) -> anyhow::Result<BTreeMap<String, String>> {
+    let content = fs::read_to_string(path).with_context(|| format!("cannot read {path}"))?;
+    let settings: BTreeMap<String, Value> =
+        serde_json::from_str(&content).with_context(|| format!("invalid settings file {path}"))?;
+    Ok(settings
+        .into_iter()
+        .map(|(key, value)| match value {
+            Value::String(value) => (key, value),
+            value => (key, value.to_string()),
+        })
+        .collect())
+}
+
+fn run(args: &Args) -> anyhow::Result<()> {
+    let file =
+        File::open(&args.input).with_context(|| format!("cannot open {}", args.input.display()))?;
+
+    let mut stdout = io::stdout().lock();
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    let mut total = 0;
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        total += 1;
+
+        let outcome = match parse_captured_event(&line) {
+            Ok(event) => replay_event(event, args.settings.clone()),
+            Err(e) => ReplayOutcome::Error {
+                code: "invalid_event".to_string(),
+                message: e.to_string(),
+            },
+        };
+        *counts.entry(outcome.code().to_string()).or_default() += 1;
+
+        let mut report = json!({ "line": i + 1 });
+        if let (Value::Object(report), Value::Object(outcome)) =
+            (&mut report, serde_json::to_value(&outcome)?)
+        {
+            report.extend(outcome);
+        }
+        writeln!(stdout, "{report}")?;
+    }
+
+    eprintln!("{total} events");
+    for (code, count) in counts.iter() {
+        eprintln!("  {code}: {count}");
+    }
+    Ok(())
+}
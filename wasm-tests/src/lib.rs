@@ -0,0 +1,106 @@
+//! Runs the compiled `linkedin_capi.wasm` component with wasmtime, through the data-collection WIT world.
+//!
+//! This crate is not linked with the component: it loads the `.wasm` file built by `make build`
+//! (or the one set in `LINKEDIN_CAPI_WASM`), like Edgee does.
+
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use wasmtime::component::{Component, Linker, ResourceTable};
+use wasmtime::{Config, Engine, Store};
+use wasmtime_wasi::{IoView, WasiCtx, WasiCtxBuilder, WasiView};
+
+wasmtime::component::bindgen!({world: "data-collection", path: "../.edgee/wit"});
+
+pub use exports::edgee::components::data_collection::{
+    Campaign, Client, Consent, Context, Data, Dict, EdgeeRequest, Event, EventType, HttpMethod,
+    PageData, Session, TrackData, UserData,
+};
+
+struct Host {
+    ctx: WasiCtx,
+    table: ResourceTable,
+}
+
+impl IoView for Host {
+    fn table(&mut self) -> &mut ResourceTable {
+        &mut self.table
+    }
+}
+
+impl WasiView for Host {
+    fn ctx(&mut self) -> &mut WasiCtx {
+        &mut self.ctx
+    }
+}
+
+/// Loaded component, with a fresh store for each call
+pub struct Harness {
+    engine: Engine,
+    component: Component,
+    linker: Linker<Host>,
+}
+
+impl Harness {
+    /// Compiled component shared by the tests
+    ///
+    /// Panics when the component has not been built.
+    pub fn get() -> &'static Self {
+        static HARNESS: OnceLock<Harness> = OnceLock::new();
+        HARNESS.get_or_init(Self::load)
+    }
+
+    fn load() -> Self {
+        let path = std::env::var("LINKEDIN_CAPI_WASM")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| {
+                PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../linkedin_capi.wasm")
+            });
+        assert!(
+            path.exists(),
+            "{} not found, run `make build` first or set LINKEDIN_CAPI_WASM",
+            path.display()
+        );
+
+        let mut config = Config::new();
+        config.wasm_component_model(true);
+        let engine = Engine::new(&config).unwrap();
+        let component = Component::from_file(&engine, &path).unwrap();
+        let mut linker = Linker::new(&engine);
+        wasmtime_wasi::add_to_linker_sync(&mut linker).unwrap();
+
+        Self {
+            engine,
+            component,
+            linker,
+        }
+    }
+
+    pub fn track(&self, event: &Event, settings: &Dict) -> Result<EdgeeRequest, String> {
+        let mut store = self.store();
+        let bindings =
+            DataCollection::instantiate(&mut store, &self.component, &self.linker).unwrap();
+        bindings
+            .edgee_components_data_collection()
+            .call_track(&mut store, event, settings)
+            .unwrap()
+    }
+
+    pub fn page(&self, event: &Event, settings: &Dict) -> Result<EdgeeRequest, String> {
+        let mut store = self.store();
+        let bindings =
+            DataCollection::instantiate(&mut store, &self.component, &self.linker).unwrap();
+        bindings
+            .edgee_components_data_collection()
+            .call_page(&mut store, event, settings)
+            .unwrap()
+    }
+
+    fn store(&self) -> Store<Host> {
+        let host = Host {
+            ctx: WasiCtxBuilder::new().inherit_stderr().build(),
+            table: ResourceTable::new(),
+        };
+        Store::new(&self.engine, host)
+    }
+}
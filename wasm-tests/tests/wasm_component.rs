@@ -0,0 +1,160 @@
+//! Tests of the compiled `linkedin_capi.wasm` component.
+//!
+//! They need the component to be built, so they are ignored by `cargo test`: run them with `make test.wasm`,
+//! or `cargo test -p linkedin-capi-wasm-tests -- --ignored` after `make build`.
+
+use linkedin_capi_wasm_tests::{
+    Campaign, Client, Consent, Context, Data, Dict, EdgeeRequest, Event, EventType, Harness,
+    HttpMethod, PageData, Session, TrackData, UserData,
+};
+
+fn sample_event(consent: Option<Consent>) -> Event {
+    Event {
+        uuid: "3f1c2b7e-8a4d-4e21-9c6b-5d0f7a9e1b23".to_string(),
+        event_type: EventType::Track,
+        timestamp: 1714566600,
+        timestamp_millis: 1714566600000,
+        timestamp_micros: 1714566600000000,
+        consent,
+        context: Context {
+            page: PageData {
+                name: "checkout".to_string(),
+                category: String::new(),
+                keywords: vec![],
+                title: "Checkout".to_string(),
+                url: "https://example.com/checkout".to_string(),
+                path: "/checkout".to_string(),
+                search: "?li_fat_id=df2a1a2b-1c5e-4c4b-8f7d-1f6f4c3b2a10".to_string(),
+                referrer: String::new(),
+                properties: vec![],
+            },
+            user: UserData {
+                user_id: "user-123".to_string(),
+                anonymous_id: String::new(),
+                edgee_id: "edgee-456".to_string(),
+                properties: vec![("email".to_string(), "john@example.com".to_string())],
+            },
+            client: Client {
+                city: String::new(),
+                ip: "81.2.69.142".to_string(),
+                locale: "en-US".to_string(),
+                timezone: String::new(),
+                user_agent: "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) Chrome/120.0.0.0"
+                    .to_string(),
+                user_agent_architecture: String::new(),
+                user_agent_bitness: String::new(),
+                user_agent_full_version_list: String::new(),
+                user_agent_version_list: String::new(),
+                user_agent_mobile: String::new(),
+                user_agent_model: String::new(),
+                os_name: String::new(),
+                os_version: String::new(),
+                screen_width: 0,
+                screen_height: 0,
+                screen_density: 0.0,
+                continent: String::new(),
+                country_code: "US".to_string(),
+                country_name: String::new(),
+                region: String::new(),
+            },
+            campaign: Campaign {
+                name: String::new(),
+                source: String::new(),
+                medium: String::new(),
+                term: String::new(),
+                content: String::new(),
+                creative_format: String::new(),
+                marketing_tactic: String::new(),
+            },
+            session: Session {
+                session_id: "session-789".to_string(),
+                previous_session_id: String::new(),
+                session_count: 1,
+                session_start: false,
+                first_seen: 0,
+                last_seen: 0,
+            },
+        },
+        data: Data::Track(TrackData {
+            name: "urn:lla:llaPartnerConversion:123".to_string(),
+            properties: vec![
+                ("value".to_string(), "49.9".to_string()),
+                ("currency".to_string(), "USD".to_string()),
+            ],
+            products: vec![],
+        }),
+    }
+}
+
+fn sample_settings() -> Dict {
+    vec![(
+        "linkedin_access_token".to_string(),
+        "test_token".to_string(),
+    )]
+}
+
+fn find_header<'a>(request: &'a EdgeeRequest, name: &str) -> Option<&'a str> {
+    request
+        .headers
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.as_str())
+}
+
+#[test]
+#[ignore = "needs the compiled component, run `make test.wasm`"]
+fn wasm_track() {
+    let harness = Harness::get();
+
+    let request = harness
+        .track(&sample_event(Some(Consent::Granted)), &sample_settings())
+        .unwrap();
+    assert_eq!(request.method, HttpMethod::Post);
+    assert_eq!(
+        request.url,
+        "https://api.linkedin.com/rest/conversionEvents"
+    );
+    assert_eq!(
+        find_header(&request, "Authorization"),
+        Some("Bearer test_token")
+    );
+
+    let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+    assert_eq!(body["conversion"], "urn:lla:llaPartnerConversion:123");
+    assert_eq!(body["conversionHappenedAt"], 1714566600000i64);
+    assert_eq!(body["conversionValue"]["amount"], "49.90");
+    assert_eq!(
+        body["user"]["userIds"][0]["idType"],
+        "LINKEDIN_FIRST_PARTY_ADS_TRACKING_UUID"
+    );
+}
+
+#[test]
+#[ignore = "needs the compiled component, run `make test.wasm`"]
+fn wasm_track_consent_denied() {
+    let harness = Harness::get();
+
+    let result = harness.track(&sample_event(Some(Consent::Denied)), &sample_settings());
+    assert_eq!(result.unwrap_err(), "Consent is not granted");
+}
+
+#[test]
+#[ignore = "needs the compiled component, run `make test.wasm`"]
+fn wasm_track_without_access_token() {
+    let harness = Harness::get();
+
+    let result = harness.track(&sample_event(Some(Consent::Granted)), &Dict::new());
+    assert!(result.is_err());
+}
+
+#[test]
+#[ignore = "needs the compiled component, run `make test.wasm`"]
+fn wasm_page_not_implemented() {
+    let harness = Harness::get();
+
+    let result = harness.page(&sample_event(Some(Consent::Granted)), &sample_settings());
+    assert_eq!(
+        result.unwrap_err(),
+        "Page event not implemented for this component"
+    );
+}